@@ -0,0 +1,237 @@
+use crate::{app::Fetched, keymap::Key};
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    RandomPage,
+    Back,
+    HintMode,
+    FollowLink,
+    FocusNextLink,
+    FocusPreviousLink,
+    LinkList,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    OpenImage,
+    NavigationMenu,
+    NextTheme,
+    Wider,
+    Narrower,
+    ToggleAlign,
+    Help,
+    CommandPalette,
+    Notifications,
+    ReadLater,
+    NextStrategy,
+    Cancel,
+}
+
+/// Every action with the name it has in the keymap file and a description.
+pub const ACTIONS: [(Action, &str, &str); 31] = [
+    (Action::Quit, "quit", "Quit"),
+    (Action::ScrollDown, "scroll_down", "Scroll down a line"),
+    (Action::ScrollUp, "scroll_up", "Scroll up a line"),
+    (
+        Action::HalfPageDown,
+        "half_page_down",
+        "Scroll down half a page",
+    ),
+    (Action::HalfPageUp, "half_page_up", "Scroll up half a page"),
+    (Action::PageDown, "page_down", "Scroll down a page"),
+    (Action::PageUp, "page_up", "Scroll up a page"),
+    (Action::Top, "top", "Go to the top of the page"),
+    (Action::Bottom, "bottom", "Go to the bottom of the page"),
+    (
+        Action::RandomPage,
+        "random_page",
+        "Open a next page, picked from the pages read",
+    ),
+    (Action::Back, "back", "Go back to the previous page"),
+    (
+        Action::HintMode,
+        "hint_mode",
+        "Show hints over the links on screen",
+    ),
+    (
+        Action::FollowLink,
+        "follow_link",
+        "Follow the selected or focused link",
+    ),
+    (
+        Action::FocusNextLink,
+        "focus_next_link",
+        "Focus the next link",
+    ),
+    (
+        Action::FocusPreviousLink,
+        "focus_previous_link",
+        "Focus the previous link",
+    ),
+    (Action::LinkList, "link_list", "List every link on the page"),
+    (Action::Search, "search", "Search the page"),
+    (Action::NextMatch, "next_match", "Jump to the next match"),
+    (
+        Action::PreviousMatch,
+        "previous_match",
+        "Jump to the previous match",
+    ),
+    (
+        Action::OpenImage,
+        "open_image",
+        "Open the image on screen in an image viewer",
+    ),
+    (
+        Action::NavigationMenu,
+        "navigation_menu",
+        "Open the navigation menu",
+    ),
+    (Action::NextTheme, "next_theme", "Switch to the next theme"),
+    (Action::Wider, "wider", "Widen the reading column"),
+    (Action::Narrower, "narrower", "Narrow the reading column"),
+    (
+        Action::ToggleAlign,
+        "toggle_align",
+        "Center the reading column or align it left",
+    ),
+    (Action::Help, "help", "Show the key bindings"),
+    (
+        Action::CommandPalette,
+        "command_palette",
+        "Search the actions and run one",
+    ),
+    (
+        Action::Notifications,
+        "notifications",
+        "Show past notifications",
+    ),
+    (
+        Action::ReadLater,
+        "read_later",
+        "Queue the selected or focused link to read later",
+    ),
+    (
+        Action::NextStrategy,
+        "next_strategy",
+        "Switch how next pages are picked",
+    ),
+    (
+        Action::Cancel,
+        "cancel",
+        "Hide hints and notifications, and clear focus and search",
+    ),
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(action, _, _)| *action)
+    }
+
+    pub fn name(self) -> &'static str {
+        ACTIONS.iter().find(|(a, _, _)| *a == self).unwrap().1
+    }
+
+    pub fn description(self) -> &'static str {
+        ACTIONS.iter().find(|(a, _, _)| *a == self).unwrap().2
+    }
+
+    /// Whether a count before the key runs the action that many times.
+    pub fn repeats(self) -> bool {
+        matches!(
+            self,
+            Action::ScrollDown
+                | Action::ScrollUp
+                | Action::HalfPageDown
+                | Action::HalfPageUp
+                | Action::PageDown
+                | Action::PageUp
+                | Action::Back
+                | Action::FocusNextLink
+                | Action::FocusPreviousLink
+                | Action::NextMatch
+                | Action::PreviousMatch
+                | Action::Wider
+                | Action::Narrower
+        )
+    }
+}
+
+/// Everything that changes the [`App`](crate::app::App), produced by the event handlers
+/// and applied by [`App::update`](crate::app::App::update).
+#[derive(Debug)]
+pub enum Message {
+    /// Terminal tick, which changes nothing on its own.
+    Tick,
+    Resize(u16, u16),
+    /// The terminal gained or lost focus, which pauses the reading time.
+    Focus(bool),
+    /// A key typed outside of panels and hint mode, looked up in the keymap.
+    Key(Key),
+    Action(Action),
+    /// Result of a background request.
+    Fetched(Fetched),
+    MoveMenuSelection {
+        up: bool,
+    },
+    /// Runs the navigation menu item at an index.
+    Navigate(usize),
+    CloseMenu,
+    /// Scrolls the help by a number of lines.
+    ScrollHelp(isize),
+    CloseHelp,
+    PaletteChar(char),
+    PaletteBackspace,
+    MovePaletteSelection(isize),
+    RunPaletteSelection,
+    ClosePalette,
+    /// Scrolls the past notifications by a number of lines.
+    ScrollNotifications(isize),
+    CloseNotifications,
+    /// Moves the selection in the session picker by a number of entries.
+    MoveSessionSelection(isize),
+    /// Resumes the selected session, or starts a new one.
+    OpenSelectedSession,
+    NewSession,
+    RenameSession,
+    SessionNameChar(char),
+    SessionNameBackspace,
+    ConfirmSessionName,
+    /// Asks to confirm deleting the selected session.
+    DeleteSession,
+    ConfirmDeleteSession,
+    CancelSessionEdit,
+    SearchChar(char),
+    SearchBackspace,
+    ToggleSearchRegex,
+    ToggleSearchCase,
+    ConfirmSearch,
+    CancelSearch,
+    MoveLinkListSelection(isize),
+    ToggleLinkOrder,
+    LinkListChar(char),
+    LinkListBackspace,
+    FollowLinkListSelection,
+    CloseLinkList,
+    HintChar(char),
+    HintBackspace,
+    /// Follows the link at an index.
+    GoToLink(usize),
+    DragScrollbar(u16),
+    ReleaseMouse,
+    Hover(u16, u16),
+    Scroll {
+        up: bool,
+    },
+}
@@ -3,42 +3,243 @@ use std::{
     error::{self, Error},
     fs::{create_dir_all, File},
     io::Write,
-    path::Path,
+    time::{Duration, Instant},
 };
 
 use crossterm::event::KeyCode;
+use image::DynamicImage;
 use ratatui::{
     layout::Rect,
-    style::{Color, Style, Stylize},
+    style::{Modifier, Style, Stylize},
     text::{Line, Span},
     widgets::ScrollbarState,
 };
 
+use tokio::sync::mpsc;
+
 use crate::{
-    flog,
-    parser::{HtmlParser, Link, Paragraph, ParagraphElement},
-    util::{base26_to_usize, usize_to_base26},
-    wikipedia::Wikipedia,
+    action::{Action, Message, ACTIONS},
+    config::Config,
+    event::Event,
+    graphics::{self, Placement, Protocol},
+    keymap::{Key, Keymap},
+    layout::{self, Anchor, ColumnAlign, Layout, LinkArea},
+    links::{link_title, sentence_around, LinkEntry, LinkList},
+    metrics::DwellTimer,
+    notifications::{Notification, Notifications, Severity},
+    palette::Palette,
+    parser::{HtmlParser, Image, Link, Paragraph, ParagraphElement, ParagraphKind},
+    search::{Match, Search},
+    session::{Session, SessionPicker, Visit},
+    strategy::{self, Context, NextPageStrategy},
+    theme::Theme,
+    util::{hint_labels, parse_hint_alphabet, DEFAULT_HINT_ALPHABET},
+    wikipedia::{Summary, Wikipedia},
 };
 
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Narrowest the reading column can be made at runtime.
+const MIN_COLUMN_WIDTH: u16 = 20;
+/// Columns the reading column grows or shrinks by at a time.
+const WIDTH_STEP: i32 = 4;
+
+/// Results of background requests, sent back as [`Event::Fetched`].
+#[derive(Debug)]
+pub enum Fetched {
+    Image(String, Option<DynamicImage>),
+    Summary(String, Option<Summary>),
+    /// The page a link points to.
+    Page(Result<String, Notification>),
+    /// The page picked to come next.
+    NextPage(Result<String, Notification>),
+    /// Something to tell about a background task, like an image viewer that failed.
+    Notification(Notification),
+}
+
+/// Preview of the page a link points to.
+pub enum Preview {
+    Loading,
+    Loaded(Summary),
+    Failed,
+}
+
+enum ImageState {
+    Loading,
+    Failed,
+    Loaded {
+        image: DynamicImage,
+        /// Size in cells at the current width.
+        size: (u16, u16),
+        /// Half block rendering at the current width, empty for other protocols.
+        lines: Vec<Line<'static>>,
+    },
+}
+
+/// The page rendered to lines, see [`App::render_text`].
+pub struct PageText<'a> {
+    pub lines: Vec<Line<'a>>,
+    /// For every line, the link number each of its spans belongs to.
+    pub span_links: Vec<Vec<Option<usize>>>,
+    pub images: Vec<ImageSlot>,
+    /// Index of the first line of every paragraph.
+    pub paragraph_starts: Vec<usize>,
+}
+
+/// Everything the layout of the page depends on, besides the page itself. The layout is
+/// only built again when this changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LayoutKey {
+    /// Bumped whenever the page or its images change.
+    page_version: u64,
+    width: u16,
+    theme: String,
+    selected_link: Option<usize>,
+    focused_link: Option<usize>,
+    hovered_link: Option<usize>,
+    matches: Vec<Match>,
+    current_match: Option<usize>,
+}
+
+/// An image in a [`PageText`], starting at line `line`.
+#[derive(Debug, Clone)]
+pub struct ImageSlot {
+    pub src: String,
+    pub line: usize,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+/// Actions in the menu that opens when clicking the title bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    Back,
+    RandomPage,
+    Top,
+    Bottom,
+}
+
+pub const NAVIGATION_MENU: [(Navigation, &str); 4] = [
+    (Navigation::Back, "Back"),
+    (Navigation::RandomPage, "Next page"),
+    (Navigation::Top, "Top of page"),
+    (Navigation::Bottom, "End of page"),
+];
+
 pub struct App {
     pub running: bool,
     // TODO: make a struct for pages with title, paragraphs, and links.
     pub paragraphs: Vec<Paragraph>,
     pub page_title: String,
-    links: HashMap<String, Link>,
+    /// Links on the page in document order.
+    links: Vec<Link>,
+    /// The sentence every link is in.
+    link_contexts: Vec<String>,
+    /// How many links on the page point to the same page as every link.
+    link_counts: Vec<usize>,
+    /// The panel listing every link, if it is open.
+    pub link_list: Option<LinkList>,
     pub vertical_scroll: usize,
     pub vertical_scroll_state: ScrollbarState,
     pub frame_size: Rect,
     page_content_length: usize,
     wikipedia: Wikipedia,
     pub selector: String,
+    /// Whether link hints are shown over the links on screen.
+    pub hint_mode: bool,
+    /// Labels of the links that were on screen when entering hint mode.
+    hints: Vec<(String, usize)>,
+    /// Characters hint labels are made of.
+    pub hint_alphabet: Vec<char>,
+    /// Keys bound to actions outside of hint mode and panels.
+    pub keymap: Keymap,
+    pub config: Config,
+    pub theme: Theme,
+    /// Maximum width of the reading column, 0 for the whole width.
+    pub max_width: u16,
+    pub align: ColumnAlign,
+    pub graphics: Protocol,
+    images: HashMap<String, ImageState>,
+    /// Previews of link targets, by link.
+    previews: HashMap<String, Preview>,
+    /// Images currently on screen, updated on every render.
+    pub image_placements: Vec<Placement>,
+    /// Where background requests send their results.
+    pub events: mpsc::UnboundedSender<Event>,
+    /// Whether the state changed since the last draw.
+    pub dirty: bool,
+    /// Whether a page is being fetched.
+    pub loading: bool,
+    /// When the current page was opened.
+    page_opened: Instant,
+    /// Picks the page that comes next.
+    strategy: Box<dyn NextPageStrategy>,
+    /// Time spent reading the current page, recorded in the session.
+    dwell: DwellTimer,
+    /// Minutes on the current page shown in the status bar, which is drawn again when they
+    /// change.
+    page_minutes: u64,
+    /// The page wrapped to the content width, updated on every render.
+    pub layout: Layout,
+    /// First line of every paragraph in the layout.
+    paragraph_starts: Vec<usize>,
+    /// Images in the layout.
+    image_slots: Vec<ImageSlot>,
+    /// What the layout was built for.
+    layout_key: Option<LayoutKey>,
+    /// Bumped whenever the page or its images change, see [`LayoutKey`].
+    page_version: u64,
+    /// Position at the top of the screen, which stays there when the page is wrapped
+    /// again, e.g. after a resize.
+    anchor: Option<Anchor>,
+    /// Scroll position the anchor was taken at, to tell whether the page was scrolled since.
+    anchored_scroll: usize,
+    /// Where the page content is drawn on screen.
+    pub content_area: Rect,
+    pub hovered_link: Option<usize>,
+    /// Link focused with tab, followed with enter.
+    pub focused_link: Option<usize>,
+    /// Whether the mouse is dragging the scrollbar.
+    scrollbar_drag: bool,
+    /// Search in the page, if one is active.
+    pub search: Option<Search>,
+    /// Selected item of the navigation menu, if it is open.
+    pub menu: Option<usize>,
+    /// First line of the key bindings shown in the help, if it is open.
+    pub help: Option<usize>,
+    /// The command palette, if it is open.
+    pub palette: Option<Palette>,
+    pub notifications: Notifications,
+    /// First of the past notifications shown, if they are.
+    pub notification_log: Option<usize>,
+    /// Session the pages opened are recorded in, if any.
+    pub session: Option<Session>,
+    /// The list of sessions shown on startup, if it is open.
+    pub session_picker: Option<SessionPicker>,
+    /// Where to scroll the page being resumed to once it is received.
+    resume_anchor: Option<Anchor>,
+    html: String,
+    /// Previously visited pages with the position at the top of the screen, most recent
+    /// last.
+    history: Vec<(String, Option<Anchor>)>,
 }
 
 impl Default for App {
     fn default() -> Self {
+        Self::with_config(Config::default())
+    }
+}
+
+impl App {
+    /// Constructs a new instance of [`App`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs an [`App`] with the settings of a validated [`Config`].
+    pub fn with_config(config: Config) -> Self {
+        // Results are dropped until the sender of an event handler is set.
+        let (events, _) = mpsc::unbounded_channel();
         Self {
             running: true,
             paragraphs: vec![],
@@ -52,31 +253,398 @@ impl Default for App {
                 height: 0,
             },
             page_content_length: 0,
-            wikipedia: Wikipedia::new(),
+            wikipedia: Wikipedia::new(&config.site, &config.user_email),
             selector: String::new(),
-            links: HashMap::new(),
+            hint_mode: false,
+            hints: vec![],
+            hint_alphabet: parse_hint_alphabet(&config.hint_alphabet)
+                .unwrap_or_else(|_| DEFAULT_HINT_ALPHABET.chars().collect()),
+            keymap: Keymap::default(),
+            links: vec![],
+            link_contexts: vec![],
+            link_counts: vec![],
+            link_list: None,
+            graphics: Protocol::from_name(&config.images).unwrap_or(Protocol::None),
+            images: HashMap::new(),
+            previews: HashMap::new(),
+            image_placements: vec![],
+            events,
+            dirty: true,
+            loading: false,
+            page_opened: Instant::now(),
+            page_minutes: 0,
+            strategy: strategy::strategy_named(&config.strategy)
+                .unwrap_or_else(|| Box::new(strategy::SessionModel)),
+            dwell: DwellTimer::new(Instant::now()),
+            layout: Layout::default(),
+            paragraph_starts: vec![],
+            image_slots: vec![],
+            layout_key: None,
+            page_version: 0,
+            anchor: None,
+            anchored_scroll: 0,
+            content_area: Rect::default(),
+            hovered_link: None,
+            focused_link: None,
+            scrollbar_drag: false,
+            search: None,
+            menu: None,
+            help: None,
+            palette: None,
+            notifications: Notifications::default(),
+            notification_log: None,
+            session: None,
+            session_picker: None,
+            resume_anchor: None,
+            html: String::new(),
+            history: vec![],
+            theme: config
+                .theme_named(&config.theme)
+                .unwrap_or_else(|_| Theme::light()),
+            max_width: config.max_width,
+            align: config.align,
+            config,
         }
     }
-}
 
-impl App {
-    /// Constructs a new instance of [`App`].
-    pub fn new() -> Self {
-        Self::default()
+    pub fn init(&mut self) {
+        if self.session_picker.is_none() && self.page_title.is_empty() && !self.loading {
+            // TODO: put page struct (see other todo) in an optional
+            self.new_page();
+        }
     }
 
-    pub async fn init(&mut self) -> Result<(), Box<dyn Error>> {
-        if self.page_title.len() == 0 {
-            // TODO: put page struct (see other todo) in an optional
-            self.new_page().await
+    /// Applies a message to the state, marking it dirty unless nothing on screen changed.
+    pub fn update(&mut self, message: Message) -> AppResult<()> {
+        self.dirty |= !matches!(
+            message,
+            Message::Tick | Message::Hover(..) | Message::Focus(_)
+        );
+        let now = Instant::now();
+        match message {
+            Message::Tick | Message::Resize(..) | Message::Fetched(_) => (),
+            Message::Focus(focused) => self.dwell.focus(focused, now),
+            _ => self.dwell.input(now),
+        }
+        match message {
+            Message::Tick => {
+                self.record_dwell(now);
+                self.dirty |= self.notifications.expire(now);
+                let minutes = self.time_on_page().as_secs() / 60;
+                if minutes != self.page_minutes && self.status_bar_shown() {
+                    self.page_minutes = minutes;
+                    self.dirty = true;
+                }
+            }
+            Message::Resize(width, height) => self.resize(width, height),
+            Message::Focus(_) => (),
+            Message::Key(key) => self.press(key),
+            Message::Action(action) => self.run(action),
+            Message::Fetched(fetched) => self.receive(fetched),
+            Message::MoveMenuSelection { up } => self.move_menu_selection(up),
+            Message::Navigate(index) => self.navigate(index),
+            Message::CloseMenu => self.close_menu(),
+            Message::ScrollHelp(delta) => self.scroll_help(delta),
+            Message::CloseHelp => self.help = None,
+            Message::PaletteChar(c) => self.palette.iter_mut().for_each(|p| p.push(c)),
+            Message::PaletteBackspace => self.palette.iter_mut().for_each(|p| p.pop()),
+            Message::MovePaletteSelection(delta) => self
+                .palette
+                .iter_mut()
+                .for_each(|p| p.move_selection(delta)),
+            Message::RunPaletteSelection => {
+                if let Some(action) = self.palette.take().and_then(|p| p.selection()) {
+                    self.run(action);
+                }
+            }
+            Message::ClosePalette => self.palette = None,
+            Message::ScrollNotifications(delta) => {
+                if let Some(first) = self.notification_log {
+                    let last = self.notifications.history().len().saturating_sub(1);
+                    let first = (first as isize + delta).clamp(0, last as isize);
+                    self.notification_log = Some(first as usize);
+                }
+            }
+            Message::CloseNotifications => self.notification_log = None,
+            Message::MoveSessionSelection(delta) => self
+                .session_picker
+                .iter_mut()
+                .for_each(|p| p.move_selection(delta)),
+            Message::OpenSelectedSession => self.open_selected_session(),
+            Message::NewSession => self.start_session(Session::new()),
+            Message::RenameSession => self.session_picker.iter_mut().for_each(|p| {
+                p.renaming = p.selection().map(|s| s.name.clone());
+            }),
+            Message::SessionNameChar(c) => self
+                .session_picker
+                .iter_mut()
+                .for_each(|p| p.renaming.iter_mut().for_each(|name| name.push(c))),
+            Message::SessionNameBackspace => self.session_picker.iter_mut().for_each(|p| {
+                p.renaming.iter_mut().for_each(|name| {
+                    name.pop();
+                })
+            }),
+            Message::ConfirmSessionName => self.confirm_session_name(),
+            Message::DeleteSession => self
+                .session_picker
+                .iter_mut()
+                .for_each(|p| p.deleting = p.selection().is_some()),
+            Message::ConfirmDeleteSession => self.delete_selected_session(),
+            Message::CancelSessionEdit => self.session_picker.iter_mut().for_each(|p| {
+                p.renaming = None;
+                p.deleting = false;
+            }),
+            Message::SearchChar(c) => self.edit_search(|s| s.query.push(c)),
+            Message::SearchBackspace => {
+                if self.search.as_ref().is_some_and(|s| s.query.is_empty()) {
+                    self.cancel_search();
+                } else {
+                    self.edit_search(|s| {
+                        s.query.pop();
+                    });
+                }
+            }
+            Message::ToggleSearchRegex => self.edit_search(|s| s.regex = !s.regex),
+            Message::ToggleSearchCase => self.edit_search(|s| s.case_sensitive = !s.case_sensitive),
+            Message::ConfirmSearch => self.confirm_search(),
+            Message::CancelSearch => self.cancel_search(),
+            Message::MoveLinkListSelection(delta) => self.move_link_list_selection(delta),
+            Message::ToggleLinkOrder => self.link_list.iter_mut().for_each(|l| l.toggle_order()),
+            Message::LinkListChar(c) => self.link_list.iter_mut().for_each(|l| l.push(c)),
+            Message::LinkListBackspace => self.link_list.iter_mut().for_each(|l| l.pop()),
+            Message::FollowLinkListSelection => self.follow_link_list_selection(),
+            Message::CloseLinkList => self.close_link_list(),
+            Message::HintChar(c) => self.link_select(c),
+            Message::HintBackspace => self.delete_link_selector(),
+            Message::GoToLink(index) => self.go_to_link(index),
+            Message::DragScrollbar(y) => self.drag_scrollbar(y),
+            Message::ReleaseMouse => self.release_mouse(),
+            Message::Hover(x, y) => {
+                let hovered = self.hovered_link;
+                self.hover(x, y);
+                self.dirty |= self.hovered_link != hovered;
+            }
+            Message::Scroll { up } => self.scroll_by(1, up),
+        }
+        Ok(())
+    }
+
+    /// Looks a key up in the keymap, running the action once it is complete.
+    fn press(&mut self, key: Key) {
+        if let Some((action, count)) = self.keymap.feed(key) {
+            let times = if action.repeats() { count } else { 1 };
+            for _ in 0..times {
+                self.run(action);
+            }
+        }
+    }
+
+    /// Runs an action bound to a key.
+    fn run(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.quit(),
+            Action::ScrollDown => self.scroll(KeyCode::Down),
+            Action::ScrollUp => self.scroll(KeyCode::Up),
+            Action::HalfPageDown => self.scroll_half_page(false),
+            Action::HalfPageUp => self.scroll_half_page(true),
+            Action::PageDown => self.scroll(KeyCode::PageDown),
+            Action::PageUp => self.scroll(KeyCode::PageUp),
+            Action::Top => self.scroll(KeyCode::Home),
+            Action::Bottom => self.scroll(KeyCode::End),
+            Action::RandomPage => self.new_page(),
+            Action::Back => self.back(),
+            Action::HintMode => self.enter_hint_mode(),
+            Action::FollowLink => self.go_to_selected_link(),
+            Action::FocusNextLink => self.focus_next_link(false),
+            Action::FocusPreviousLink => self.focus_next_link(true),
+            Action::LinkList => self.open_link_list(),
+            Action::Search => self.start_search(),
+            Action::NextMatch => self.next_match(false),
+            Action::PreviousMatch => self.next_match(true),
+            Action::OpenImage => self.open_image(),
+            Action::NavigationMenu => self.open_menu(),
+            Action::NextTheme => self.next_theme(),
+            Action::Wider => self.change_max_width(WIDTH_STEP),
+            Action::Narrower => self.change_max_width(-WIDTH_STEP),
+            Action::ToggleAlign => self.toggle_align(),
+            Action::Help => self.help = Some(0),
+            Action::CommandPalette => self.palette = Some(Palette::new()),
+            Action::Notifications => self.notification_log = Some(0),
+            Action::ReadLater => self.read_later(),
+            Action::NextStrategy => self.next_strategy(),
+            Action::Cancel => {
+                self.notifications.dismiss();
+                self.exit_hint_mode();
+                self.clear_focus();
+                self.cancel_search();
+            }
+        }
+    }
+
+    /// Shows a notification over the page.
+    pub fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        self.notifications
+            .push(Notification::new(severity, message));
+        self.dirty = true;
+    }
+
+    /// Applies the result of a background request.
+    fn receive(&mut self, fetched: Fetched) {
+        match fetched {
+            Fetched::Image(src, Some(image)) => {
+                let state = self.fit_image(image);
+                self.images.insert(src, state);
+                self.page_version += 1;
+                // Images move the lines after them, and the matches on those lines.
+                self.refresh_search();
+            }
+            Fetched::Image(src, None) => {
+                self.images.insert(src, ImageState::Failed);
+                self.page_version += 1;
+            }
+            Fetched::Summary(link, summary) => {
+                let preview = summary.map_or(Preview::Failed, Preview::Loaded);
+                self.previews.insert(link, preview);
+            }
+            Fetched::Page(html) => {
+                self.loading = false;
+                let resume_anchor = self.resume_anchor.take();
+                match html {
+                    Ok(html) => {
+                        self.push_history();
+                        self.set_html(&html);
+                        // Scrolled to on the next render, like after a resize.
+                        self.anchor = resume_anchor;
+                    }
+                    Err(notification) => self.notifications.push(notification),
+                }
+            }
+            Fetched::NextPage(html) => {
+                self.loading = false;
+                if let Some(session) = &mut self.session {
+                    session.fetched_read_later(html.is_ok());
+                }
+                match html {
+                    Ok(html) => {
+                        self.push_history();
+                        self.set_html(&html);
+                        if let Err(e) = self.save_html(&html) {
+                            self.notify(Severity::Warning, format!("Cannot save page: {e}"));
+                        }
+                    }
+                    Err(notification) => self.notifications.push(notification),
+                }
+            }
+            Fetched::Notification(notification) => self.notifications.push(notification),
+        }
+    }
+
+    /// Resumes the selected session where it was left, or starts a new one if the new
+    /// session entry is selected.
+    fn open_selected_session(&mut self) {
+        let Some(mut picker) = self.session_picker.take() else {
+            return;
+        };
+        if picker.selected < picker.sessions.len() {
+            self.start_session(picker.sessions.swap_remove(picker.selected));
         } else {
-            Ok(())
+            self.start_session(Session::new());
+        }
+    }
+
+    /// Closes the session picker and records pages in `session` from now on, opening the
+    /// last page it visited or a random one.
+    fn start_session(&mut self, session: Session) {
+        self.session_picker = None;
+        if let Some(visit) = session.last_visit() {
+            self.resume_anchor = Some(visit.anchor);
+            self.open_page(&visit.title.replace(' ', "_"));
+        }
+        self.session = Some(session);
+        self.init();
+    }
+
+    fn confirm_session_name(&mut self) {
+        let Some(picker) = &mut self.session_picker else {
+            return;
+        };
+        let (Some(name), Some(session)) = (
+            picker.renaming.take(),
+            picker.sessions.get_mut(picker.selected),
+        ) else {
+            return;
+        };
+        if name.trim().is_empty() {
+            return;
+        }
+        session.name = name.trim().to_string();
+        if let Err(e) = session.save(&self.config.session_dir) {
+            self.notify(Severity::Error, e);
         }
     }
 
-    /// Handles the tick event of the terminal.
-    pub fn tick(&mut self) {
-        // self.page_title = format!("{:?}", self.page_content_length);
+    fn delete_selected_session(&mut self) {
+        let Some(picker) = &mut self.session_picker else {
+            return;
+        };
+        picker.deleting = false;
+        if picker.selected >= picker.sessions.len() {
+            return;
+        }
+        let session = picker.sessions.remove(picker.selected);
+        if let Err(e) = session.delete(&self.config.session_dir) {
+            picker.sessions.insert(picker.selected, session);
+            self.notify(Severity::Error, e);
+        }
+    }
+
+    /// Saves the session with where the current page is scrolled to. Sessions without
+    /// pages are not saved.
+    pub fn save_session(&mut self) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        if let Some(anchor) = self.anchor {
+            session.scroll_to(anchor);
+        }
+        if session.visits.is_empty() {
+            return;
+        }
+        if let Err(e) = session.save(&self.config.session_dir) {
+            self.notify(Severity::Warning, e);
+        }
+    }
+
+    /// The visit of the current page in the session, if one is recorded.
+    fn current_visit(&mut self) -> Option<&mut Visit> {
+        self.session
+            .as_mut()?
+            .visits
+            .last_mut()
+            .filter(|visit| visit.title == self.page_title)
+    }
+
+    /// Adds the time spent reading since the last call to the current page.
+    fn record_dwell(&mut self, now: Instant) {
+        let read = self.dwell.take(now);
+        if let Some(visit) = self.current_visit() {
+            visit.metrics.dwell += read.as_secs_f64();
+        }
+    }
+
+    /// Records how far the current page was read, once it was laid out.
+    fn record_depth(&mut self) {
+        let laid_out = self
+            .layout_key
+            .as_ref()
+            .is_some_and(|key| key.page_version == self.page_version);
+        if !laid_out || self.layout.rows.is_empty() {
+            return;
+        }
+        let depth = self.progress();
+        if let Some(visit) = self.current_visit() {
+            visit.metrics.depth = visit.metrics.depth.max(depth);
+        }
     }
 
     /// Set running to false to quit the application.
@@ -85,61 +653,628 @@ impl App {
     }
 
     pub fn resize(&mut self, width: u16, height: u16) {
+        let content_width = self.content_width();
         self.frame_size.width = width;
         self.frame_size.height = height;
+        if self.content_width() != content_width {
+            self.refit_images();
+        }
+    }
+
+    /// Area between the title bar and the status bar, left of the scrollbar.
+    pub fn page_area(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 1,
+            width: self.frame_size.width.saturating_sub(1),
+            height: self
+                .frame_size
+                .height
+                .saturating_sub(1 + self.status_bar_shown() as u16),
+        }
+    }
+
+    pub fn status_bar_shown(&self) -> bool {
+        !self.config.status_bar.is_empty()
+    }
+
+    /// Area of the page the text is wrapped in.
+    pub fn column_area(&self) -> Rect {
+        layout::column(self.page_area(), self.max_width, self.align)
+    }
+
+    /// The last column of the screen, below the title bar.
+    pub fn scrollbar_area(&self) -> Rect {
+        let page = self.page_area();
+        Rect {
+            x: page.x + page.width,
+            width: 1,
+            ..page
+        }
+    }
+
+    fn content_width(&self) -> u16 {
+        self.column_area().width
+    }
+
+    /// Makes the reading column `delta` columns wider, taking the whole width once it
+    /// is wider than the screen.
+    pub fn change_max_width(&mut self, delta: i32) {
+        let content_width = self.content_width();
+        let page_width = self.page_area().width as i32;
+        let width = (content_width as i32 + delta).max(MIN_COLUMN_WIDTH as i32);
+        self.max_width = if width >= page_width { 0 } else { width as u16 };
+        if self.content_width() != content_width {
+            self.refit_images();
+        }
+    }
+
+    pub fn toggle_align(&mut self) {
+        self.align = self.align.toggle();
+    }
+
+    /// Fits the loaded images to the content width again.
+    fn refit_images(&mut self) {
+        let images = std::mem::take(&mut self.images);
+        self.images = images
+            .into_iter()
+            .map(|(src, state)| match state {
+                ImageState::Loaded { image, .. } => (src, self.fit_image(image)),
+                state => (src, state),
+            })
+            .collect();
+        self.page_version += 1;
+    }
+
+    fn fit_image(&self, image: DynamicImage) -> ImageState {
+        let (columns, rows) = self.graphics.fit(&image, self.content_width());
+        let lines = if self.graphics == Protocol::HalfBlocks && columns > 0 && rows > 0 {
+            graphics::half_blocks(&image, columns, rows)
+        } else {
+            vec![]
+        };
+        ImageState::Loaded {
+            image,
+            size: (columns, rows),
+            lines,
+        }
+    }
+
+    fn fetch_image(&mut self, src: &str) {
+        if self.images.contains_key(src) {
+            return;
+        }
+        self.images.insert(src.to_string(), ImageState::Loading);
+
+        let wikipedia = self.wikipedia.clone();
+        let sender = self.events.clone();
+        let src = src.to_string();
+        tokio::spawn(async move {
+            let image = match wikipedia.get_bytes(&graphics::absolute_url(&src)).await {
+                Ok(bytes) => image::load_from_memory(&bytes).ok(),
+                Err(_) => None,
+            };
+            let _ = sender.send(Event::Fetched(Fetched::Image(src, image)));
+        });
+    }
+
+    /// Returns the decoded image with the given `src`, if it finished loading.
+    pub fn image(&self, src: &str) -> Option<&DynamicImage> {
+        match self.images.get(src) {
+            Some(ImageState::Loaded { image, .. }) => Some(image),
+            _ => None,
+        }
+    }
+
+    /// Downloads the full size version of the topmost image on screen and opens it in the
+    /// default image viewer.
+    pub fn open_image(&self) {
+        let Some(placement) = self.image_placements.first() else {
+            return;
+        };
+        let url = graphics::full_size_url(&placement.src);
+        // Kept in a temporary directory, as they are only downloaded for the viewer.
+        let dir = std::env::temp_dir().join("clikipedia-images");
+        let path = dir.join(url.rsplit('/').next().unwrap_or("image"));
+        let wikipedia = self.wikipedia.clone();
+        let sender = self.events.clone();
+
+        tokio::spawn(async move {
+            let notification = match wikipedia.get_bytes(&url).await {
+                Ok(bytes) => {
+                    let result = create_dir_all(&dir)
+                        .and_then(|_| std::fs::write(&path, bytes))
+                        .and_then(|_| graphics::open_external(&path));
+                    match result {
+                        Ok(()) => {
+                            Notification::new(Severity::Info, format!("Opened {}", path.display()))
+                        }
+                        Err(e) => Notification::failed("Cannot open image", &e),
+                    }
+                }
+                Err(e) => Notification::failed("Cannot download image", &*e),
+            };
+            let _ = sender.send(Event::Fetched(Fetched::Notification(notification)));
+        });
     }
 
     pub fn link_select(&mut self, c: char) {
-        if self.link_selector_exists() | self.selector.is_empty() {
-            self.selector.push(c);
+        let selector = format!("{}{c}", self.selector);
+        if self
+            .hints
+            .iter()
+            .any(|(label, _)| label.starts_with(&selector))
+        {
+            self.selector = selector;
         }
+        self.fetch_preview();
+    }
+
+    /// Whether `c` types a hint label.
+    pub fn is_hint_char(&self, c: char) -> bool {
+        self.hint_mode && self.hint_alphabet.contains(&c)
     }
 
     pub fn delete_link_selector(&mut self) {
-        self.selector.pop();
+        if self.selector.pop().is_none() {
+            self.exit_hint_mode();
+        }
+        self.fetch_preview();
+    }
+
+    pub fn link_count(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Heading of the section at the top of the screen.
+    pub fn current_section(&self) -> Option<String> {
+        let top = self
+            .layout
+            .anchor_at(self.vertical_scroll, &self.paragraph_starts)
+            .paragraph;
+        let heading = self
+            .paragraphs
+            .iter()
+            .take(top + 1)
+            .rev()
+            .find(|p| p.kind == ParagraphKind::Heading)?;
+        Some(
+            heading
+                .elems
+                .iter()
+                .map(|e| match e {
+                    ParagraphElement::Text(text, _) => text.as_str(),
+                    ParagraphElement::Link(link) => link.text.as_str(),
+                    ParagraphElement::Image(_) => "",
+                })
+                .collect(),
+        )
+    }
+
+    /// Percentage of the page that was on screen, up to the bottom of the screen.
+    pub fn progress(&self) -> usize {
+        let rows = self.layout.rows.len();
+        if rows == 0 {
+            return 100;
+        }
+        let bottom = (self.vertical_scroll + self.content_area.height as usize).min(rows);
+        bottom * 100 / rows
+    }
+
+    /// Number of images on the page that are still being fetched, and of all of them.
+    pub fn images_loading(&self) -> (usize, usize) {
+        let states = self
+            .paragraphs
+            .iter()
+            .flat_map(|p| &p.elems)
+            .filter_map(|e| match e {
+                ParagraphElement::Image(image) => self.images.get(&image.src),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let loading = states
+            .iter()
+            .filter(|state| matches!(state, ImageState::Loading))
+            .count();
+        (loading, states.len())
+    }
+
+    pub fn time_on_page(&self) -> Duration {
+        self.page_opened.elapsed()
+    }
+
+    /// Labels the links that are on screen, so that short labels can be used to select them.
+    pub fn enter_hint_mode(&mut self) {
+        self.hint_mode = true;
+        self.selector.clear();
+        let links = self.visible_links();
+        let labels = hint_labels(links.len(), &self.hint_alphabet);
+        self.hints = labels
+            .into_iter()
+            .zip(links)
+            .map(|(label, (link, _))| (label, link))
+            .collect();
+    }
+
+    pub fn exit_hint_mode(&mut self) {
+        self.hint_mode = false;
+        self.selector.clear();
+        self.hints.clear();
+    }
+
+    /// Links with a part on screen, with the first visible area of each.
+    fn visible_links(&self) -> Vec<(usize, LinkArea)> {
+        let rows = self.vertical_scroll..self.vertical_scroll + self.content_area.height as usize;
+        self.layout
+            .link_areas
+            .iter()
+            .enumerate()
+            .filter_map(|(link, areas)| {
+                let area = areas.iter().find(|a| rows.contains(&a.row))?;
+                Some((link, *area))
+            })
+            .collect()
+    }
+
+    /// Returns the label of every hint that still matches the selector, with the screen
+    /// position of the link it belongs to.
+    pub fn hint_labels(&self) -> Vec<(String, u16, u16)> {
+        let visible = self.visible_links();
+        self.hints
+            .iter()
+            .filter(|(label, _)| label.starts_with(&self.selector))
+            .filter_map(|(label, link)| {
+                let (_, area) = visible.iter().find(|(l, _)| l == link)?;
+                let x = self.content_area.x + area.x;
+                let y = self.content_area.y + (area.row - self.vertical_scroll) as u16;
+                Some((label.clone(), x, y))
+            })
+            .collect()
+    }
+
+    /// Starts fetching the preview of the selected link, unless it is already cached.
+    fn fetch_preview(&mut self) {
+        let Some(link) = self.selected_link().map(|i| self.links[i].link.clone()) else {
+            return;
+        };
+        if self.previews.contains_key(&link) {
+            return;
+        }
+        self.previews.insert(link.clone(), Preview::Loading);
+
+        let wikipedia = self.wikipedia.clone();
+        let sender = self.events.clone();
+        tokio::spawn(async move {
+            let summary = wikipedia.summary(&link).await.ok();
+            let _ = sender.send(Event::Fetched(Fetched::Summary(link, summary)));
+        });
+    }
+
+    /// Returns the selected link with the preview of its target, if one was requested.
+    pub fn selected_preview(&self) -> Option<(&Link, &Preview)> {
+        let link = &self.links[self.selected_link()?];
+        Some((link, self.previews.get(&link.link)?))
     }
 
-    pub async fn go_to_selected_link(&mut self) {
-        if let Some(link) = self.links.get(&self.selector) {
-            self.selector = String::new();
-            // TODO: this await blocks the whole app, should not be awaited but there should be some sort of callback and some state and a loading icon
-            let html = self.wikipedia.get_page(&link.link).await;
-            match html {
-                Ok(html) => self.set_html(&html),
-                Err(_) => todo!(),
+    pub fn go_to_selected_link(&mut self) {
+        if let Some(index) = self.selected_link() {
+            self.exit_hint_mode();
+            self.go_to_link(index);
+        } else if let Some(index) = self.focused_link {
+            self.go_to_link(index);
+        }
+    }
+
+    /// Moves the focus to the next link in document order, or the previous one if
+    /// `backwards`, and scrolls it into view. Without focus, it starts at the links on screen.
+    pub fn focus_next_link(&mut self, backwards: bool) {
+        if self.links.is_empty() {
+            return;
+        }
+        let first_row = |link: usize| {
+            self.layout
+                .link_areas
+                .get(link)
+                .and_then(|areas| areas.first())
+                .map_or(0, |a| a.row)
+        };
+        let last = self.links.len() - 1;
+        let focus = match (self.focused_link, backwards) {
+            (Some(focus), false) => (focus + 1).min(last),
+            (Some(focus), true) => focus.saturating_sub(1),
+            (None, false) => (0..=last)
+                .find(|&l| first_row(l) >= self.vertical_scroll)
+                .unwrap_or(last),
+            (None, true) => {
+                let bottom = self.vertical_scroll + self.content_area.height as usize;
+                (0..=last)
+                    .rev()
+                    .find(|&l| first_row(l) < bottom)
+                    .unwrap_or(0)
             }
+        };
+        self.focused_link = Some(focus);
+
+        let row = first_row(focus);
+        let height = (self.content_area.height as usize).max(1);
+        if row < self.vertical_scroll {
+            self.vertical_scroll = row;
+        } else if row >= self.vertical_scroll + height {
+            self.vertical_scroll = row + 1 - height;
+        }
+        self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
+    }
+
+    pub fn clear_focus(&mut self) {
+        self.focused_link = None;
+    }
+
+    pub fn go_to_link(&mut self, index: usize) {
+        let link = self.links[index].link.clone();
+        if let Some(visit) = self.current_visit() {
+            visit.metrics.followed.push(link_title(&link));
         }
+        self.open_page(&link);
+    }
+
+    /// Starts fetching a page by its title, which replaces the current one once it is
+    /// received.
+    pub fn open_page(&mut self, page: &str) {
+        let page = page.to_string();
+        let wikipedia = self.wikipedia.clone();
+        let sender = self.events.clone();
+        self.loading = true;
+        tokio::spawn(async move {
+            let html = wikipedia
+                .get_page(&page)
+                .await
+                .map_err(|e| Notification::failed("Cannot load page", &*e));
+            let _ = sender.send(Event::Fetched(Fetched::Page(html)));
+        });
+    }
+
+    /// Index of the link the selector points to.
+    fn selected_link(&self) -> Option<usize> {
+        if self.selector.is_empty() {
+            return None;
+        }
+        self.hints
+            .iter()
+            .find(|(label, _)| *label == self.selector)
+            .map(|(_, link)| *link)
     }
 
     pub fn link_selector_exists(&self) -> bool {
-        self.links.get(&self.selector).is_some()
+        self.selected_link().is_some()
+    }
+
+    fn push_history(&mut self) {
+        if !self.html.is_empty() {
+            let html = std::mem::take(&mut self.html);
+            self.history.push((html, self.anchor));
+        }
+    }
+
+    /// Goes back to the previously visited page, where it was scrolled to.
+    pub fn back(&mut self) {
+        if let Some((html, anchor)) = self.history.pop() {
+            self.show_html(&html, true);
+            // Scrolled to on the next render, which may wrap the page differently.
+            self.anchor = anchor;
+        }
+    }
+
+    /// Returns the link on the screen cell at `x`, `y`.
+    pub fn link_at(&self, x: u16, y: u16) -> Option<usize> {
+        let area = self.content_area;
+        if x < area.x || x >= area.x + area.width || y < area.y || y >= area.y + area.height {
+            return None;
+        }
+        let row = self.vertical_scroll + (y - area.y) as usize;
+        self.layout.link_at(row, x - area.x)
+    }
+
+    pub fn hover(&mut self, x: u16, y: u16) {
+        self.hovered_link = self.link_at(x, y);
+    }
+
+    /// Whether the screen cell at `x`, `y` is on the scrollbar.
+    pub fn on_scrollbar(&self, x: u16, y: u16) -> bool {
+        let area = self.scrollbar_area();
+        self.content_area.area() > 0 && x == area.x && y >= area.y && y < area.y + area.height
+    }
+
+    /// Starts or continues dragging the scrollbar, jumping to the position at row `y`.
+    pub fn drag_scrollbar(&mut self, y: u16) {
+        self.scrollbar_drag = true;
+        let area = self.content_area;
+        let fraction =
+            y.saturating_sub(area.y) as f64 / area.height.saturating_sub(1).max(1) as f64;
+        let max_scroll = self.page_content_length.saturating_sub(5);
+        self.vertical_scroll = ((fraction * max_scroll as f64).round() as usize).min(max_scroll);
+        self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
+    }
+
+    pub fn dragging_scrollbar(&self) -> bool {
+        self.scrollbar_drag
+    }
+
+    pub fn release_mouse(&mut self) {
+        self.scrollbar_drag = false;
+    }
+
+    /// Scrolls the help by `delta` lines, keeping at least one binding in view.
+    pub fn scroll_help(&mut self, delta: isize) {
+        if let Some(first) = self.help {
+            let first = (first as isize + delta).clamp(0, ACTIONS.len() as isize - 1);
+            self.help = Some(first as usize);
+        }
+    }
+
+    pub fn open_menu(&mut self) {
+        self.menu = Some(0);
+    }
+
+    pub fn close_menu(&mut self) {
+        self.menu = None;
+    }
+
+    pub fn move_menu_selection(&mut self, up: bool) {
+        if let Some(selected) = self.menu {
+            self.menu = Some(if up {
+                selected.saturating_sub(1)
+            } else {
+                (selected + 1).min(NAVIGATION_MENU.len() - 1)
+            });
+        }
+    }
+
+    pub fn open_link_list(&mut self) {
+        self.link_list = Some(LinkList::new());
+    }
+
+    pub fn close_link_list(&mut self) {
+        self.link_list = None;
+    }
+
+    /// Entries of the link list that match its query.
+    pub fn link_list_entries(&self) -> Vec<LinkEntry<'_>> {
+        match &self.link_list {
+            Some(list) => list.entries(&self.links, &self.link_contexts, &self.link_counts),
+            None => vec![],
+        }
+    }
+
+    pub fn move_link_list_selection(&mut self, delta: isize) {
+        let count = self.link_list_entries().len();
+        if let Some(list) = &mut self.link_list {
+            list.move_selection(delta, count);
+        }
+    }
+
+    /// Follows the selected link of the link list and closes it.
+    pub fn follow_link_list_selection(&mut self) {
+        let selected = self.link_list.as_ref().map_or(0, |l| l.selected);
+        let link = self.link_list_entries().get(selected).map(|e| e.index);
+        if let Some(link) = link {
+            self.link_list = None;
+            self.go_to_link(link);
+        }
+    }
+
+    pub fn start_search(&mut self) {
+        self.search = Some(Search::new());
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Finishes typing the search query, an empty query cancels the search.
+    pub fn confirm_search(&mut self) {
+        match &mut self.search {
+            Some(search) if search.query.is_empty() => self.search = None,
+            Some(search) => search.editing = false,
+            None => (),
+        }
+    }
+
+    /// Changes the search and jumps to the first match from the top of the screen, so the
+    /// search is incremental.
+    pub fn edit_search(&mut self, edit: impl FnOnce(&mut Search)) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        edit(search);
+        self.refresh_search();
+
+        let line = self.layout.line_of(self.vertical_scroll);
+        if let Some(found) = self
+            .search
+            .as_mut()
+            .and_then(|s| s.select_from(line, false))
+        {
+            self.scroll_to_match(found);
+        }
+    }
+
+    /// Finds the matches of the search in the current page text again.
+    fn refresh_search(&mut self) {
+        if self.search.is_none() {
+            return;
+        }
+        let lines = self
+            .get_text()
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect::<Vec<String>>();
+        if let Some(search) = &mut self.search {
+            let current = search.current;
+            search.find(&lines);
+            search.current = current.filter(|&i| i < search.matches.len());
+        }
+    }
+
+    /// Jumps to the next match of the search, or the previous one if `backwards`.
+    pub fn next_match(&mut self, backwards: bool) {
+        if let Some(found) = self.search.as_mut().and_then(|s| s.select_next(backwards)) {
+            self.scroll_to_match(found);
+        }
+    }
+
+    /// Scrolls the row a match was wrapped to into view, a third from the top.
+    fn scroll_to_match(&mut self, found: Match) {
+        let row = self.layout.row_of(found.line, found.start);
+        let height = self.content_area.height as usize;
+        if row < self.vertical_scroll || row >= self.vertical_scroll + height {
+            self.vertical_scroll = row.saturating_sub(height / 3);
+            self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
+        }
+    }
+
+    /// Runs the navigation menu item at `index` and closes the menu.
+    pub fn navigate(&mut self, index: usize) {
+        self.menu = None;
+        match NAVIGATION_MENU[index].0 {
+            Navigation::Back => self.back(),
+            Navigation::RandomPage => self.new_page(),
+            Navigation::Top => self.scroll(KeyCode::Home),
+            Navigation::Bottom => self.scroll(KeyCode::End),
+        }
     }
 
     pub fn scroll(&mut self, key: KeyCode) {
-        enum Direction {
-            Up,
-            Down,
-        }
-
-        let (amount, direction) = match key {
-            KeyCode::Home => (usize::MAX, Direction::Up),
-            KeyCode::End => (usize::MAX, Direction::Down),
-            KeyCode::Up => (1, Direction::Up),
-            KeyCode::Down => (1, Direction::Down),
-            KeyCode::PageUp => (self.frame_size.height as usize - 2, Direction::Up),
-            KeyCode::PageDown => (self.frame_size.height as usize - 2, Direction::Down),
+        let page = (self.page_area().height as usize).saturating_sub(1);
+        match key {
+            KeyCode::Home => self.scroll_by(usize::MAX, true),
+            KeyCode::End => self.scroll_by(usize::MAX, false),
+            KeyCode::Up => self.scroll_by(1, true),
+            KeyCode::Down => self.scroll_by(1, false),
+            KeyCode::PageUp => self.scroll_by(page, true),
+            KeyCode::PageDown => self.scroll_by(page, false),
             _ => panic!("Passed non-scroll key into scroll function."),
-        };
+        }
+    }
 
-        match direction {
-            Direction::Down => {
-                self.vertical_scroll = (self.vertical_scroll + amount).min(
-                    self.page_content_length.saturating_sub(5),
-                    // .saturating_sub(self.frame_size.height as usize  / 2),
-                )
-            }
-            Direction::Up => self.vertical_scroll = self.vertical_scroll.saturating_sub(amount),
+    pub fn scroll_half_page(&mut self, up: bool) {
+        self.scroll_by((self.page_area().height as usize / 2).max(1), up);
+    }
+
+    fn scroll_by(&mut self, amount: usize, up: bool) {
+        if up {
+            self.vertical_scroll = self.vertical_scroll.saturating_sub(amount);
+        } else {
+            self.vertical_scroll = self
+                .vertical_scroll
+                .saturating_add(amount)
+                .min(self.page_content_length.saturating_sub(5));
+        }
+
+        // Other links are on screen now, so they need new labels.
+        if self.hint_mode {
+            self.enter_hint_mode();
         }
+        self.record_depth();
 
         self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
     }
@@ -149,85 +1284,433 @@ impl App {
         self.page_content_length = length;
     }
 
+    /// Wraps the page to `area` with the search matches highlighted, reusing the previous
+    /// layout when nothing it depends on changed. Returns whether it was wrapped again.
+    pub fn layout_page(&mut self, area: Rect) -> bool {
+        let key = LayoutKey {
+            page_version: self.page_version,
+            width: area.width,
+            theme: self.theme.name.clone(),
+            selected_link: self.selected_link(),
+            focused_link: self.focused_link,
+            hovered_link: self.hovered_link,
+            matches: self
+                .search
+                .as_ref()
+                .map_or(vec![], |search| search.matches.clone()),
+            current_match: self.search.as_ref().and_then(|search| search.current),
+        };
+        if self.layout_key.as_ref() == Some(&key) {
+            self.content_area = area;
+            self.keep_anchor();
+            self.record_depth();
+            return false;
+        }
+
+        let PageText {
+            lines,
+            span_links,
+            images,
+            paragraph_starts,
+        } = self.render_text();
+        let mut layout = Layout::wrap(&lines, &span_links, area.width);
+        drop(lines);
+        for (i, m) in key.matches.iter().enumerate() {
+            let style = if key.current_match == Some(i) {
+                self.theme.current_match
+            } else {
+                self.theme.search_match
+            };
+            layout.highlight(m.line, m.start, m.end, style);
+        }
+        self.image_slots = images;
+        self.set_layout(layout, area, paragraph_starts);
+        self.layout_key = Some(key);
+        self.record_depth();
+        true
+    }
+
+    /// Images in the current layout.
+    pub fn image_slots(&self) -> &[ImageSlot] {
+        &self.image_slots
+    }
+
+    /// Stores the layout of the current render, used to find what is under the mouse.
+    pub fn set_layout(&mut self, layout: Layout, area: Rect, paragraph_starts: Vec<usize>) {
+        self.set_scroll_params(layout.rows.len());
+        self.layout = layout;
+        self.content_area = area;
+        self.paragraph_starts = paragraph_starts;
+        self.layout_key = None;
+        self.keep_anchor();
+    }
+
+    /// When the page was not scrolled since the last render, scrolls it to keep the same
+    /// text at the top of the screen, even if it was wrapped differently. Otherwise anchors
+    /// the text that was scrolled to.
+    fn keep_anchor(&mut self) {
+        match self.anchor {
+            Some(anchor) if self.vertical_scroll == self.anchored_scroll => {
+                self.vertical_scroll = self.layout.row_of_anchor(anchor, &self.paragraph_starts);
+                self.vertical_scroll_state =
+                    self.vertical_scroll_state.position(self.vertical_scroll);
+            }
+            _ => {
+                self.anchor = Some(
+                    self.layout
+                        .anchor_at(self.vertical_scroll, &self.paragraph_starts),
+                )
+            }
+        }
+        self.anchored_scroll = self.vertical_scroll;
+    }
+
     pub fn set_html(&mut self, html: &str) {
+        self.show_html(html, false);
+    }
+
+    /// Shows the page `html`, recorded in the session as a new visit, or when going `back`
+    /// to it as its last visit again.
+    fn show_html(&mut self, html: &str, back: bool) {
         let page = HtmlParser::parse_page(html);
+        self.record_dwell(Instant::now());
+        if let (Some(session), Some(anchor)) = (&mut self.session, self.anchor) {
+            session.scroll_to(anchor);
+        }
 
+        self.html = html.to_string();
         self.page_title = page.title;
         self.paragraphs = page.paragraphs.clone();
 
-        let mut num_links = 0;
         self.links.clear();
+        self.link_contexts.clear();
         for p in &self.paragraphs {
+            let mut text = String::new();
+            let mut ranges = vec![];
             for e in &p.elems {
                 match e {
+                    ParagraphElement::Text(t, _) => text.push_str(t),
                     ParagraphElement::Link(link) => {
-                        self.links.insert(usize_to_base26(num_links), link.clone());
-                        num_links += 1;
+                        let start = text.len();
+                        text.push_str(&link.text);
+                        ranges.push((link, start, text.len()));
                     }
-                    _ => (),
+                    ParagraphElement::Image(_) => (),
                 }
             }
+            for (link, start, end) in ranges {
+                self.links.push(link.clone());
+                self.link_contexts
+                    .push(sentence_around(&text, start, end).to_string());
+            }
+        }
+        // Links to different sections of a page count as links to the same page.
+        let titles = self
+            .links
+            .iter()
+            .map(|l| link_title(&l.link))
+            .collect::<Vec<_>>();
+        let mut counts = HashMap::new();
+        for title in &titles {
+            *counts.entry(title.as_str()).or_insert(0) += 1;
+        }
+        self.link_counts = titles.iter().map(|t| counts[t.as_str()]).collect();
+        self.hovered_link = None;
+        self.focused_link = None;
+        self.link_list = None;
+        self.search = None;
+        self.exit_hint_mode();
+
+        if self.graphics != Protocol::None {
+            let sources = self
+                .paragraphs
+                .iter()
+                .flat_map(|p| &p.elems)
+                .filter_map(|e| match e {
+                    ParagraphElement::Image(image) => Some(image.src.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            for src in sources {
+                self.fetch_image(&src);
+            }
         }
-        flog!(self.links);
 
         self.vertical_scroll = 0;
         self.vertical_scroll_state = ScrollbarState::default();
+        self.anchor = None;
+        self.anchored_scroll = 0;
+        self.page_version += 1;
+        self.page_opened = Instant::now();
+        self.page_minutes = 0;
+        if let Some(session) = &mut self.session {
+            if back {
+                session.revisit(&self.page_title);
+            } else {
+                session.visit(&self.page_title);
+            }
+        }
+        self.save_session();
     }
 
-    pub async fn new_page(&mut self) -> Result<(), Box<dyn Error>> {
-        let html = self.wikipedia.random_page().await?;
-        self.set_html(&html);
+    /// Starts fetching a next page, which replaces the current one once it is received. It
+    /// is picked by the strategy.
+    pub fn new_page(&mut self) {
+        let next = self.strategy.next_page(Context {
+            wikipedia: &self.wikipedia,
+            session: self.session.as_mut(),
+            page_title: &self.page_title,
+            exploration: self.config.exploration,
+        });
+        let sender = self.events.clone();
+        self.loading = true;
+        tokio::spawn(async move {
+            let html = next
+                .await
+                .map_err(|e| Notification::failed("Cannot load next page", &*e));
+            let _ = sender.send(Event::Fetched(Fetched::NextPage(html)));
+        });
+    }
 
-        let path_str = &format!("htmls/{}.html", self.page_title);
-        let path = Path::new(path_str.as_str());
+    /// Saves the html of the current page to the html directory.
+    fn save_html(&self, html: &str) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .config
+            .html_dir
+            .join(format!("{}.html", self.page_title));
         if let Some(parent) = path.parent() {
             create_dir_all(parent)?;
         }
-        let mut file = File::create(path)?;
+        let mut file = File::create(&path)?;
         file.write_all(html.as_bytes())?;
 
         Ok(())
     }
 
+    /// Name of the strategy that picks next pages.
+    pub fn strategy_name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    /// Switches to the built-in strategy after the current one.
+    pub fn next_strategy(&mut self) {
+        let mut strategies = strategy::built_in();
+        let current = strategies
+            .iter()
+            .position(|s| s.name() == self.strategy.name());
+        let next = current.map_or(0, |i| (i + 1) % strategies.len());
+        self.strategy = strategies.swap_remove(next);
+        let message = format!("Next pages: {}", self.strategy.description());
+        self.notify(Severity::Info, message);
+    }
+
+    /// Queues the link selected with a hint or focused to read later in the session.
+    pub fn read_later(&mut self) {
+        let Some(index) = self.selected_link().or(self.focused_link) else {
+            self.notify(Severity::Info, "Select or focus a link to read it later");
+            return;
+        };
+        let title = link_title(&self.links[index].link);
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        session.read_later(&title);
+        let count = session.read_later.len();
+        self.notify(
+            Severity::Info,
+            format!("Read later: {title} ({count} queued)"),
+        );
+        self.exit_hint_mode();
+    }
+
+    /// Switches to the theme after the current one, built-in themes first.
+    pub fn next_theme(&mut self) {
+        let names = self.config.theme_names();
+        let current = names.iter().position(|n| *n == self.theme.name);
+        let next = &names[current.map_or(0, |i| (i + 1) % names.len())];
+        if let Ok(theme) = self.config.theme_named(next) {
+            self.theme = theme;
+        }
+    }
+
     pub fn get_text(&self) -> Vec<Line<'_>> {
+        self.render_text().lines
+    }
+
+    /// Renders the page to lines, along with the links and images in those lines.
+    pub fn render_text(&self) -> PageText<'_> {
+        let theme = &self.theme;
         let mut link_counter = 0;
-        let mut lines = vec![];
+        let mut text = PageText {
+            lines: vec![],
+            span_links: vec![],
+            images: vec![],
+            paragraph_starts: vec![],
+        };
         for paragraph in &self.paragraphs {
+            text.paragraph_starts.push(text.lines.len());
+            if paragraph.kind == ParagraphKind::Figure {
+                for elem in &paragraph.elems {
+                    if let ParagraphElement::Image(image) = elem {
+                        self.push_image(image, &mut text);
+                    }
+                }
+                text.lines.push(Line::from(vec![]));
+                text.span_links.push(vec![]);
+                continue;
+            }
+
             // let mut line_vec = vec![Span::raw(format!("{:?}: ", paragraph.elems))];
-            let mut line_vec = vec![];
+            let mut line_vec = match paragraph.kind {
+                ParagraphKind::Body
+                | ParagraphKind::Heading
+                | ParagraphKind::Caption
+                | ParagraphKind::Figure => vec![],
+                ParagraphKind::Hatnote => vec![Span::raw("    ")],
+                ParagraphKind::Quote => vec![Span::styled("  │ ", theme.quote)],
+            };
+            let mut span_links = vec![None; line_vec.len()];
+            let block_style = match paragraph.kind {
+                ParagraphKind::Body | ParagraphKind::Figure => Style::default(),
+                ParagraphKind::Heading => theme.heading,
+                ParagraphKind::Quote => theme.quote,
+                ParagraphKind::Hatnote | ParagraphKind::Caption => theme.note,
+            };
+            let italic = block_style.add_modifier.contains(Modifier::ITALIC);
             for elem in &paragraph.elems {
                 match elem {
-                    ParagraphElement::Text(text, false) => line_vec.push(Span::raw(text)),
-                    ParagraphElement::Text(text, true) => line_vec.push(Span::raw(text).italic()),
+                    ParagraphElement::Text(text, emphasis) => {
+                        // Emphasis inside an already italic block is shown upright, like browsers do.
+                        let style = match (*emphasis, italic) {
+                            (true, true) => block_style.not_italic(),
+                            (true, false) => block_style.italic(),
+                            (false, _) => block_style,
+                        };
+                        line_vec.push(Span::styled(text, style));
+                        span_links.push(None);
+                    }
                     ParagraphElement::Link(Link { link: _, text }) => {
-                        // TODO: if link counter = to_usize(selector) then this is selected
-                        let selected = !self.selector.is_empty()
-                            && link_counter == base26_to_usize(&self.selector);
-                        let mut style = Style::default();
-                        if selected {
-                            style = style.bg(Color::Blue).fg(Color::White);
+                        let selected = self.selected_link() == Some(link_counter)
+                            || self.focused_link == Some(link_counter);
+                        let style = block_style.patch(if selected {
+                            theme.selected_link
+                        } else if self.hovered_link == Some(link_counter) {
+                            theme.hovered_link
                         } else {
-                            style = style.fg(Color::Blue).underlined();
-                        }
+                            theme.link
+                        });
                         let link = Span::styled(text, style);
                         line_vec.push(link);
-                        line_vec.append(&mut self.format_link_ref(link_counter, style));
+                        span_links.push(Some(link_counter));
                         link_counter += 1;
                     }
+                    ParagraphElement::Image(_) => (),
                 };
             }
-            if !line_vec.is_empty() {
-                lines.push(Line::from(line_vec));
-                lines.push(Line::from(vec![]));
+            if !paragraph.elems.is_empty() {
+                text.lines.push(Line::from(line_vec));
+                text.lines.push(Line::from(vec![]));
+                text.span_links.push(span_links);
+                text.span_links.push(vec![]);
             }
         }
-        lines.clone()
+        text
     }
 
-    fn format_link_ref(&self, link_counter: usize, style: Style) -> Vec<Span> {
-        vec![Span::styled(
-            format!("[{}]", usize_to_base26(link_counter)),
-            style,
-        )]
+    /// Pushes the lines of an image, or its alt text if it cannot be shown.
+    fn push_image<'a>(&'a self, image: &Image, text: &mut PageText<'a>) {
+        match self.images.get(&image.src) {
+            Some(ImageState::Loaded {
+                size: (columns, rows),
+                lines: rendered,
+                ..
+            }) if *columns > 0 && *rows > 0 => {
+                text.images.push(ImageSlot {
+                    src: image.src.clone(),
+                    line: text.lines.len(),
+                    columns: *columns,
+                    rows: *rows,
+                });
+                if rendered.is_empty() {
+                    // Drawn over these lines by the terminal graphics protocol.
+                    text.lines.extend((0..*rows).map(|_| Line::from(vec![])));
+                } else {
+                    text.lines.extend(rendered.iter().cloned());
+                }
+                text.span_links.extend((0..*rows).map(|_| vec![]));
+            }
+            _ => {
+                text.lines.push(Line::from(
+                    Span::raw(format!("[image: {}]", image.alt)).italic(),
+                ));
+                text.span_links.push(vec![]);
+            }
+        }
     }
 }
+
+#[test]
+fn test_anchor_across_widths() {
+    let mut app = App::new();
+    let words = (0..200).map(|i| format!("word{i}")).collect::<Vec<_>>();
+    app.set_html(&format!(
+        "<html><body><p>{}</p><p>{}</p></body></html>",
+        words.join(" "),
+        words.join(" ")
+    ));
+    let rewrap = |app: &mut App, width| {
+        let text = app.render_text();
+        let layout = Layout::wrap(&text.lines, &text.span_links, width);
+        let paragraph_starts = text.paragraph_starts;
+        app.set_layout(layout, Rect::default(), paragraph_starts);
+    };
+
+    rewrap(&mut app, 30);
+    app.vertical_scroll = 70;
+    rewrap(&mut app, 30);
+    let anchor = app.anchor.unwrap();
+    assert_eq!(anchor.paragraph, 1);
+
+    rewrap(&mut app, 70);
+    assert!(app.vertical_scroll < 70);
+    let row = app.vertical_scroll;
+    assert_eq!(
+        app.layout.anchor_at(row, &app.paragraph_starts).paragraph,
+        1
+    );
+    assert!(app.layout.row_offsets[row] <= anchor.offset);
+    assert!(app.layout.row_offsets.get(row + 1).unwrap() > &anchor.offset);
+
+    rewrap(&mut app, 30);
+    assert_eq!(app.vertical_scroll, 70);
+
+    // Going back from another page lands on the same text, even at another width.
+    app.push_history();
+    app.set_html("<html><body><p>Another page</p></body></html>");
+    rewrap(&mut app, 30);
+    app.back();
+    rewrap(&mut app, 70);
+    assert_eq!(app.vertical_scroll, row);
+}
+
+#[test]
+fn test_layout_cache() {
+    let mut app = App::new();
+    let html = "<html><body><p>See <a rel=\"mw:WikiLink\" href=\"./Lyon\">Lyon</a> and \
+                <a rel=\"mw:WikiLink\" href=\"./Paris\">Paris</a></p></body></html>";
+    app.set_html(html);
+    let area = Rect::new(0, 1, 30, 20);
+    assert!(app.layout_page(area));
+    assert!(!app.layout_page(area));
+
+    // The layout is only built again when something it depends on changes.
+    assert!(app.layout_page(Rect { width: 31, ..area }));
+    assert!(!app.layout_page(Rect { width: 31, ..area }));
+    app.next_theme();
+    assert!(app.layout_page(area));
+    app.focus_next_link(false);
+    assert!(app.layout_page(area));
+    assert!(!app.layout_page(area));
+    app.set_html(html);
+    assert!(app.layout_page(area));
+}
@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphics::Protocol,
+    layout::ColumnAlign,
+    status::StatusSegment,
+    strategy::{names, strategy_named},
+    theme::{Theme, ThemeSpec},
+    util::{config_dir, data_dir, parse_hint_alphabet, DEFAULT_HINT_ALPHABET},
+};
+
+/// Settings from `config.toml` in the config directory, overridden by command line flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Wikipedia pages are read from, e.g. `de.wikipedia.org` for the German one.
+    pub site: String,
+    /// E-mail address sent in the user agent, as Wikimedia asks of API clients. Defaults
+    /// to the `USER_EMAIL` environment variable.
+    pub user_email: String,
+    /// Milliseconds between ticks. The screen is only drawn again when something changed.
+    pub tick_rate: u64,
+    /// Directory every visited page is saved to.
+    pub html_dir: PathBuf,
+    /// Directory sessions are saved to, `sessions` in the data directory by default.
+    pub session_dir: PathBuf,
+    /// File `flog!` writes to.
+    pub log_file: PathBuf,
+    /// How to draw images: `auto`, `none`, `halfblocks`, `sixel` or `kitty`.
+    pub images: String,
+    /// Characters link hint labels are made of.
+    pub hint_alphabet: String,
+    /// Maximum width of the text in columns, 0 for the whole width of the terminal.
+    pub max_width: u16,
+    /// Whether the text is centered or aligned left when the terminal is wider.
+    pub align: ColumnAlign,
+    /// How next pages are picked: `session`, `read_later`, `random`, `related`, `category`
+    /// or `links`.
+    pub strategy: String,
+    /// How far next pages stray from the pages read, from 0 for pages close to the most
+    /// interesting ones to 1 for mostly random pages.
+    pub exploration: f64,
+    /// Segments of the status bar from left to right, none to hide it.
+    pub status_bar: Vec<StatusSegment>,
+    /// Name of the theme, built-in or defined in `themes`.
+    pub theme: String,
+    /// User defined themes, by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, ThemeSpec>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            site: "en.wikipedia.org".to_string(),
+            user_email: env::var("USER_EMAIL").unwrap_or_default(),
+            tick_rate: 250,
+            html_dir: PathBuf::from("htmls"),
+            session_dir: data_dir()
+                .map_or_else(|| PathBuf::from("sessions"), |dir| dir.join("sessions")),
+            log_file: PathBuf::from("log.txt"),
+            images: "auto".to_string(),
+            hint_alphabet: DEFAULT_HINT_ALPHABET.to_string(),
+            max_width: 100,
+            align: ColumnAlign::Center,
+            strategy: "session".to_string(),
+            exploration: 0.3,
+            status_bar: StatusSegment::DEFAULT.to_vec(),
+            theme: "light".to_string(),
+            themes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Path of the config file, `config.toml` in the config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Loads a config file, with defaults for the settings it leaves out.
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config {}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid config {}: {e}", path.display()))
+    }
+
+    /// Checks the settings that are not checked by parsing.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.site.is_empty() || self.site.contains('/') {
+            return Err(format!(
+                "Site '{}' should be a host name like en.wikipedia.org",
+                self.site
+            ));
+        }
+        if self.user_email.is_empty() {
+            return Err(
+                "No e-mail address set for the user agent. Set user_email in \
+                 config.toml, pass --user-email or set USER_EMAIL."
+                    .to_string(),
+            );
+        }
+        if !self.user_email.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(format!(
+                "E-mail address '{}' cannot be sent in a header",
+                self.user_email
+            ));
+        }
+        if self.tick_rate == 0 {
+            return Err("Tick rate should be at least 1 ms".to_string());
+        }
+        if Protocol::from_name(&self.images).is_none() {
+            return Err(format!(
+                "Unknown image protocol '{}', expected auto, none, halfblocks, sixel or kitty",
+                self.images
+            ));
+        }
+        if strategy_named(&self.strategy).is_none() {
+            return Err(format!(
+                "Unknown strategy '{}', expected one of {}",
+                self.strategy,
+                names().join(", ")
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.exploration) {
+            return Err(format!(
+                "Exploration {} should be between 0 and 1",
+                self.exploration
+            ));
+        }
+        parse_hint_alphabet(&self.hint_alphabet)?;
+        for name in self.theme_names() {
+            self.theme_named(&name)?;
+        }
+        self.theme_named(&self.theme)?;
+        Ok(())
+    }
+
+    /// Names of the built-in themes followed by the user defined ones.
+    pub fn theme_names(&self) -> Vec<String> {
+        let mut names = Theme::BUILT_IN.map(String::from).to_vec();
+        for name in self.themes.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// Looks up a theme, user defined themes hiding built-in ones with the same name.
+    pub fn theme_named(&self, name: &str) -> Result<Theme, String> {
+        match self.themes.get(name) {
+            Some(spec) => Theme::from_spec(name, spec),
+            None => Theme::built_in(name).ok_or_else(|| {
+                format!(
+                    "Unknown theme '{name}', expected one of {}",
+                    self.theme_names().join(", ")
+                )
+            }),
+        }
+    }
+
+    /// Writes the configuration back as the contents of a config file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Config is always valid TOML")
+    }
+}
+
+#[test]
+fn test_config() {
+    let config: Config = toml::from_str(
+        "site = \"de.wikipedia.org\"\nuser_email = \"a@b.c\"\ntheme = \"mine\"\n\
+         [themes.mine]\nbase = \"dark\"\nlink = { fg = \"#ff8800\" }",
+    )
+    .unwrap();
+    assert_eq!(config.site, "de.wikipedia.org");
+    assert_eq!(config.tick_rate, 250);
+    assert!(config.validate().is_ok());
+    assert_eq!(
+        config.theme_names(),
+        vec!["light", "dark", "terminal", "mine"]
+    );
+
+    let printed: Config = toml::from_str(&config.to_toml()).unwrap();
+    assert_eq!(printed.theme_named("mine"), config.theme_named("mine"));
+    assert!(Config {
+        theme: "sepia".to_string(),
+        ..config
+    }
+    .validate()
+    .is_err());
+    assert!(toml::from_str::<Config>("tickrate = 100").is_err());
+}
@@ -4,10 +4,10 @@ use crossterm::event::{Event as CrosstermEvent, KeyEvent, MouseEvent};
 use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 
-use crate::app::AppResult;
+use crate::app::{AppResult, Fetched};
 
-/// Terminal events.
-#[derive(Clone, Copy, Debug)]
+/// Terminal events, and results of background requests.
+#[derive(Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick,
@@ -17,6 +17,10 @@ pub enum Event {
     Mouse(MouseEvent),
     /// Terminal resize.
     Resize(u16, u16),
+    /// Terminal focus gained or lost.
+    Focus(bool),
+    /// Result of a background request.
+    Fetched(Fetched),
 }
 
 /// Terminal event handler.
@@ -50,7 +54,11 @@ impl EventHandler {
                   Some(Ok(evt)) = crossterm_event => {
                     match evt {
                       CrosstermEvent::Key(key) => {
-                        if key.kind == crossterm::event::KeyEventKind::Press {
+                        // Modifiers pressed on their own are reported with the kitty
+                        // keyboard protocol, but only modify the next key.
+                        if key.kind == crossterm::event::KeyEventKind::Press
+                          && !matches!(key.code, crossterm::event::KeyCode::Modifier(_))
+                        {
                           _sender.send(Event::Key(key)).unwrap();
                         }
                       },
@@ -61,8 +69,10 @@ impl EventHandler {
                         _sender.send(Event::Resize(x, y)).unwrap();
                       },
                       CrosstermEvent::FocusLost => {
+                        _sender.send(Event::Focus(false)).unwrap();
                       },
                       CrosstermEvent::FocusGained => {
+                        _sender.send(Event::Focus(true)).unwrap();
                       },
                       CrosstermEvent::Paste(_) => {
                       },
@@ -78,6 +88,11 @@ impl EventHandler {
         }
     }
 
+    /// Returns a sender for background tasks to send their results with.
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.sender.clone()
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
@@ -86,9 +101,6 @@ impl EventHandler {
         self.receiver
             .recv()
             .await
-            .ok_or(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "This is an IO error",
-            )))
+            .ok_or(Box::new(std::io::Error::other("This is an IO error")))
     }
 }
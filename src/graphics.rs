@@ -0,0 +1,296 @@
+use std::{env, fmt::Write, path::Path, process::Stdio};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+/// Widest an image is drawn with half blocks, in terminal cells.
+const MAX_HALF_BLOCK_COLUMNS: u16 = 48;
+/// Cell size in pixels assumed when the terminal does not report it.
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+/// Size of the base64 chunks the kitty graphics protocol accepts.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// How images are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Images are not drawn, only their alt text and caption are shown.
+    None,
+    /// Unicode upper half blocks, two pixels per cell, works in any true color terminal.
+    HalfBlocks,
+    /// DEC sixel graphics.
+    Sixel,
+    /// The kitty terminal graphics protocol.
+    Kitty,
+}
+
+impl Protocol {
+    /// Guesses the best protocol the terminal supports from its environment.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+        if env::var("KITTY_WINDOW_ID").is_ok()
+            || term.contains("kitty")
+            || matches!(term_program.as_str(), "WezTerm" | "ghostty")
+        {
+            Protocol::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.starts_with("contour")
+        {
+            Protocol::Sixel
+        } else {
+            Protocol::HalfBlocks
+        }
+    }
+
+    /// Parses the value of the `--images` argument, `auto` detects the protocol.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::detect()),
+            "none" => Some(Protocol::None),
+            "halfblocks" => Some(Protocol::HalfBlocks),
+            "sixel" => Some(Protocol::Sixel),
+            "kitty" => Some(Protocol::Kitty),
+            _ => None,
+        }
+    }
+
+    /// Whether images are drawn with escape sequences outside of the ratatui buffer.
+    pub fn is_pixel_based(&self) -> bool {
+        matches!(self, Protocol::Sixel | Protocol::Kitty)
+    }
+
+    /// Computes the size in cells an image takes up when it is at most `max_columns` wide.
+    pub fn fit(&self, image: &DynamicImage, max_columns: u16) -> (u16, u16) {
+        let (width, height) = image.dimensions();
+        let (width, height) = (width.max(1), height.max(1));
+        match self {
+            Protocol::None => (0, 0),
+            Protocol::HalfBlocks => {
+                let columns = (width as u16).min(max_columns).min(MAX_HALF_BLOCK_COLUMNS);
+                let pixel_rows = height * columns as u32 / width;
+                (columns, pixel_rows.div_ceil(2) as u16)
+            }
+            Protocol::Sixel | Protocol::Kitty => {
+                let (cell_width, cell_height) = cell_size();
+                let columns = (width.div_ceil(cell_width as u32) as u16).min(max_columns);
+                let pixel_height = height * columns as u32 * cell_width as u32 / width;
+                (columns, pixel_height.div_ceil(cell_height as u32) as u16)
+            }
+        }
+    }
+}
+
+/// A visible image on screen, in cells. `first_row` is the first row of the image that is
+/// shown, so images scrolled partially out of view are cropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub src: String,
+    pub x: u16,
+    pub y: u16,
+    pub columns: u16,
+    pub rows: u16,
+    pub first_row: u16,
+    pub visible_rows: u16,
+}
+
+/// Size of a terminal cell in pixels.
+pub fn cell_size() -> (u16, u16) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Renders an image as lines of upper half blocks, `columns` cells wide.
+pub fn half_blocks(image: &DynamicImage, columns: u16, rows: u16) -> Vec<Line<'static>> {
+    let image = image
+        .resize_exact(columns as u32, rows as u32 * 2, FilterType::Triangle)
+        .to_rgb8();
+
+    (0..rows as u32)
+        .map(|row| {
+            let spans = (0..columns as u32)
+                .map(|x| {
+                    let top = image.get_pixel(x, row * 2);
+                    let bottom = image.get_pixel(x, row * 2 + 1);
+                    Span::styled(
+                        "▀",
+                        Style::default()
+                            .fg(Color::Rgb(top[0], top[1], top[2]))
+                            .bg(Color::Rgb(bottom[0], bottom[1], bottom[2])),
+                    )
+                })
+                .collect::<Vec<_>>();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Encodes the visible part of a placed image as escape sequences for `protocol`. The cursor
+/// must already be at the top left cell of the placement.
+pub fn encode(protocol: Protocol, image: &DynamicImage, placement: &Placement) -> String {
+    let (cell_width, cell_height) = cell_size();
+    let (cell_width, cell_height) = (cell_width as u32, cell_height as u32);
+    let image = image
+        .resize_exact(
+            placement.columns as u32 * cell_width,
+            placement.rows as u32 * cell_height,
+            FilterType::Triangle,
+        )
+        .crop_imm(
+            0,
+            placement.first_row as u32 * cell_height,
+            placement.columns as u32 * cell_width,
+            placement.visible_rows as u32 * cell_height,
+        );
+
+    match protocol {
+        Protocol::Sixel => sixel(&image),
+        Protocol::Kitty => kitty(&image, placement.columns, placement.visible_rows),
+        Protocol::None | Protocol::HalfBlocks => String::new(),
+    }
+}
+
+/// Escape sequence removing every image drawn with the kitty protocol.
+pub fn kitty_clear() -> &'static str {
+    "\x1b_Ga=d,d=a,q=2\x1b\\"
+}
+
+fn kitty(image: &DynamicImage, columns: u16, rows: u16) -> String {
+    let rgba = image.to_rgba8();
+    let data = STANDARD.encode(rgba.as_raw());
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect::<Vec<_>>();
+
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},c={columns},r={rows},C=1,q=2,m={more};{chunk}\x1b\\",
+                rgba.width(),
+                rgba.height(),
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out
+}
+
+/// Encodes an image as sixels, quantized to a 6x6x6 color cube.
+fn sixel(image: &DynamicImage) -> String {
+    let rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    let level = |c: u8| (c as u16 * 5 / 255) as usize;
+    let index = |x, y| {
+        let p = rgb.get_pixel(x, y);
+        level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])
+    };
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    for i in 0..216 {
+        let _ = write!(
+            out,
+            "#{i};2;{};{};{}",
+            i / 36 * 20,
+            i / 6 % 6 * 20,
+            i % 6 * 20
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_rows = (height - band).min(6);
+        let mut bits = vec![[0u8; 216]; width as usize];
+        let mut used = [false; 216];
+        for x in 0..width {
+            for dy in 0..band_rows {
+                let color = index(x, band + dy);
+                bits[x as usize][color] |= 1 << dy;
+                used[color] = true;
+            }
+        }
+
+        for color in (0..216).filter(|&c| used[c]) {
+            let _ = write!(out, "#{color}");
+            let mut x = 0;
+            while x < width as usize {
+                let value = bits[x][color];
+                let run = bits[x..].iter().take_while(|b| b[color] == value).count();
+                let c = (value + 63) as char;
+                if run > 3 {
+                    let _ = write!(out, "!{run}{c}");
+                } else {
+                    (0..run).for_each(|_| out.push(c));
+                }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Turns the `src` of a thumbnail into an absolute url.
+pub fn absolute_url(src: &str) -> String {
+    if src.starts_with("//") {
+        format!("https:{src}")
+    } else {
+        src.to_string()
+    }
+}
+
+/// Turns the url of a thumbnail into the url of the original file, by dropping the `/thumb`
+/// directory and the sized file name, e.g. `.../thumb/a/ab/Foo.jpg/220px-Foo.jpg` becomes
+/// `.../a/ab/Foo.jpg`.
+pub fn full_size_url(src: &str) -> String {
+    let url = absolute_url(src);
+    match url.split_once("/thumb/") {
+        Some((base, rest)) => match rest.rsplit_once('/') {
+            Some((original, _)) => format!("{base}/{original}"),
+            None => url,
+        },
+        None => url,
+    }
+}
+
+/// Opens a file with the default viewer of the desktop.
+pub fn open_external(path: &Path) -> std::io::Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+#[test]
+fn test_full_size_url() {
+    assert_eq!(
+        full_size_url("//upload.wikimedia.org/wikipedia/commons/thumb/a/ab/Foo.jpg/220px-Foo.jpg"),
+        "https://upload.wikimedia.org/wikipedia/commons/a/ab/Foo.jpg"
+    );
+    assert_eq!(
+        full_size_url("//upload.wikimedia.org/wikipedia/commons/a/ab/Foo.jpg"),
+        "https://upload.wikimedia.org/wikipedia/commons/a/ab/Foo.jpg"
+    );
+}
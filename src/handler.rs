@@ -1,47 +1,290 @@
-use crate::app::{App, AppResult};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
-
-/// Handles the key events and updates the state of [`App`].
-pub async fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    match key_event.code {
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
+use crate::action::{Action, Message};
+use crate::app::App;
+use crate::event::Event;
+use crate::keymap::Key;
+use crate::ui;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+/// Turns an event into the message that updates the [`App`], if it changes anything.
+pub fn handle_event(event: Event, app: &App) -> Option<Message> {
+    match event {
+        Event::Tick => Some(Message::Tick),
+        Event::Key(key_event) => handle_key_events(key_event, app),
+        Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, app),
+        Event::Resize(width, height) => Some(Message::Resize(width, height)),
+        Event::Focus(focused) => Some(Message::Focus(focused)),
+        Event::Fetched(fetched) => Some(Message::Fetched(fetched)),
+    }
+}
+
+/// Handles the key events, depending on the panel that is open. Keys match with their
+/// modifiers, so that e.g. Shift+Enter does not follow a link.
+pub fn handle_key_events(key_event: KeyEvent, app: &App) -> Option<Message> {
+    const NONE: KeyModifiers = KeyModifiers::NONE;
+    const CONTROL: KeyModifiers = KeyModifiers::CONTROL;
+
+    let key = Key::from(key_event);
+    if matches!(
+        (key.code, key.modifiers),
+        (KeyCode::Char('c' | 'C'), CONTROL)
+    ) {
+        return Some(Message::Action(Action::Quit));
+    }
+
+    if let Some(picker) = &app.session_picker {
+        if picker.renaming.is_some() {
+            if let Some(c) = key.text() {
+                return Some(Message::SessionNameChar(c));
             }
+            return match (key.code, key.modifiers) {
+                (KeyCode::Backspace, NONE) => Some(Message::SessionNameBackspace),
+                (KeyCode::Enter, NONE) => Some(Message::ConfirmSessionName),
+                (KeyCode::Esc, NONE) => Some(Message::CancelSessionEdit),
+                _ => None,
+            };
+        }
+        if picker.deleting {
+            return match (key.code, key.modifiers) {
+                (KeyCode::Char('y'), NONE) => Some(Message::ConfirmDeleteSession),
+                _ => Some(Message::CancelSessionEdit),
+            };
+        }
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up | KeyCode::Char('k'), NONE) => Some(Message::MoveSessionSelection(-1)),
+            (KeyCode::Down | KeyCode::Char('j'), NONE) => Some(Message::MoveSessionSelection(1)),
+            (KeyCode::Enter, NONE) => Some(Message::OpenSelectedSession),
+            (KeyCode::Char('n'), NONE) => Some(Message::NewSession),
+            (KeyCode::Char('r'), NONE) => Some(Message::RenameSession),
+            (KeyCode::Char('d'), NONE) => Some(Message::DeleteSession),
+            (KeyCode::Esc | KeyCode::Char('q'), NONE) => Some(Message::Action(Action::Quit)),
+            _ => None,
+        };
+    }
+
+    if app.help.is_some() {
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up | KeyCode::Char('k'), NONE) => Some(Message::ScrollHelp(-1)),
+            (KeyCode::Down | KeyCode::Char('j'), NONE) => Some(Message::ScrollHelp(1)),
+            (KeyCode::PageUp, NONE) => Some(Message::ScrollHelp(-10)),
+            (KeyCode::PageDown, NONE) => Some(Message::ScrollHelp(10)),
+            (KeyCode::Esc | KeyCode::Char('q' | '?'), NONE) => Some(Message::CloseHelp),
+            _ => None,
+        };
+    }
+
+    if app.notification_log.is_some() {
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up | KeyCode::Char('k'), NONE) => Some(Message::ScrollNotifications(-1)),
+            (KeyCode::Down | KeyCode::Char('j'), NONE) => Some(Message::ScrollNotifications(1)),
+            (KeyCode::PageUp, NONE) => Some(Message::ScrollNotifications(-10)),
+            (KeyCode::PageDown, NONE) => Some(Message::ScrollNotifications(10)),
+            (KeyCode::Esc | KeyCode::Char('q'), NONE) => Some(Message::CloseNotifications),
+            _ => None,
+        };
+    }
+
+    if app.palette.is_some() {
+        if let Some(c) = key.text() {
+            return Some(Message::PaletteChar(c));
         }
-        _ => (),
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up, NONE) => Some(Message::MovePaletteSelection(-1)),
+            (KeyCode::Down, NONE) => Some(Message::MovePaletteSelection(1)),
+            (KeyCode::Backspace, NONE) => Some(Message::PaletteBackspace),
+            (KeyCode::Enter, NONE) => Some(Message::RunPaletteSelection),
+            (KeyCode::Esc, NONE) => Some(Message::ClosePalette),
+            _ => None,
+        };
+    }
+
+    if let Some(selected) = app.menu {
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up, NONE) => Some(Message::MoveMenuSelection { up: true }),
+            (KeyCode::Down, NONE) => Some(Message::MoveMenuSelection { up: false }),
+            (KeyCode::Enter, NONE) => Some(Message::Navigate(selected)),
+            (KeyCode::Esc, NONE) => Some(Message::CloseMenu),
+            _ => None,
+        };
     }
-    // TODO: add a back key
-    match key_event.code {
-        KeyCode::Down
-        | KeyCode::Up
-        | KeyCode::PageDown
-        | KeyCode::PageUp
-        | KeyCode::Home
-        | KeyCode::End => {
-            app.scroll(key_event.code);
+
+    if app.search.as_ref().is_some_and(|s| s.editing) {
+        if let Some(c) = key.text() {
+            return Some(Message::SearchChar(c));
         }
-        KeyCode::Char(' ') => {
-            app.new_page().await?;
+        return match (key.code, key.modifiers) {
+            (KeyCode::Esc, NONE) => Some(Message::CancelSearch),
+            (KeyCode::Enter, NONE) => Some(Message::ConfirmSearch),
+            (KeyCode::Backspace, NONE) => Some(Message::SearchBackspace),
+            (KeyCode::Char('r'), CONTROL) => Some(Message::ToggleSearchRegex),
+            (KeyCode::Char('t'), CONTROL) => Some(Message::ToggleSearchCase),
+            _ => None,
+        };
+    }
+
+    if app.link_list.is_some() {
+        if let Some(c) = key.text() {
+            return Some(Message::LinkListChar(c));
         }
-        KeyCode::Char(c @ 'a'..='z') => app.link_select(c),
-        KeyCode::Backspace => app.delete_link_selector(),
-        KeyCode::Enter => app.go_to_selected_link().await,
-        _ => {}
+        return match (key.code, key.modifiers) {
+            (KeyCode::Up, NONE) => Some(Message::MoveLinkListSelection(-1)),
+            (KeyCode::Down, NONE) => Some(Message::MoveLinkListSelection(1)),
+            (KeyCode::PageUp, NONE) => Some(Message::MoveLinkListSelection(-10)),
+            (KeyCode::PageDown, NONE) => Some(Message::MoveLinkListSelection(10)),
+            (KeyCode::Tab, NONE) => Some(Message::ToggleLinkOrder),
+            (KeyCode::Backspace, NONE) => Some(Message::LinkListBackspace),
+            (KeyCode::Enter, NONE) => Some(Message::FollowLinkListSelection),
+            (KeyCode::Esc, NONE) => Some(Message::CloseLinkList),
+            _ => None,
+        };
+    }
+
+    match (key.text(), key.code, key.modifiers) {
+        (Some(c), _, _) if app.is_hint_char(c) => Some(Message::HintChar(c)),
+        (_, KeyCode::Backspace, NONE) if app.hint_mode => Some(Message::HintBackspace),
+        _ => Some(Message::Key(key)),
     }
-    Ok(())
 }
 
-pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) -> AppResult<()> {
+/// Handles the mouse events, resolving what is under the mouse.
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &App) -> Option<Message> {
+    let (x, y) = (mouse_event.column, mouse_event.row);
+    if app.session_picker.is_some() {
+        return match mouse_event.kind {
+            MouseEventKind::ScrollUp => Some(Message::MoveSessionSelection(-1)),
+            MouseEventKind::ScrollDown => Some(Message::MoveSessionSelection(1)),
+            _ => None,
+        };
+    }
+    // Panels cover the page, which is neither clicked nor scrolled through them. A click
+    // next to a panel closes it, like it does the navigation menu.
+    let outside = !ui::on_panel(app.frame_size, x, y);
+    if app.help.is_some() {
+        return match mouse_event.kind {
+            MouseEventKind::ScrollDown => Some(Message::ScrollHelp(1)),
+            MouseEventKind::ScrollUp => Some(Message::ScrollHelp(-1)),
+            MouseEventKind::Down(_) if outside => Some(Message::CloseHelp),
+            _ => None,
+        };
+    }
+    if app.notification_log.is_some() {
+        return match mouse_event.kind {
+            MouseEventKind::ScrollDown => Some(Message::ScrollNotifications(1)),
+            MouseEventKind::ScrollUp => Some(Message::ScrollNotifications(-1)),
+            MouseEventKind::Down(_) if outside => Some(Message::CloseNotifications),
+            _ => None,
+        };
+    }
+    if app.palette.is_some() {
+        return match mouse_event.kind {
+            MouseEventKind::ScrollDown => Some(Message::MovePaletteSelection(1)),
+            MouseEventKind::ScrollUp => Some(Message::MovePaletteSelection(-1)),
+            MouseEventKind::Down(_) if outside => Some(Message::ClosePalette),
+            _ => None,
+        };
+    }
+    if app.link_list.is_some() {
+        return match mouse_event.kind {
+            MouseEventKind::ScrollDown => Some(Message::MoveLinkListSelection(1)),
+            MouseEventKind::ScrollUp => Some(Message::MoveLinkListSelection(-1)),
+            MouseEventKind::Down(_) if outside => Some(Message::CloseLinkList),
+            _ => None,
+        };
+    }
     match mouse_event.kind {
-        crossterm::event::MouseEventKind::Down(_) => {}
-        crossterm::event::MouseEventKind::Up(_) => {}
-        crossterm::event::MouseEventKind::Drag(_) => {}
-        crossterm::event::MouseEventKind::Moved => {}
-        crossterm::event::MouseEventKind::ScrollDown => app.scroll(KeyCode::Down),
-        crossterm::event::MouseEventKind::ScrollUp => app.scroll(KeyCode::Up),
-        crossterm::event::MouseEventKind::ScrollLeft => {}
-        crossterm::event::MouseEventKind::ScrollRight => {}
-    }
-    Ok(())
+        MouseEventKind::Down(MouseButton::Left) => {
+            if app.menu.is_some() {
+                match ui::menu_item_at(x, y) {
+                    Some(item) => Some(Message::Navigate(item)),
+                    None => Some(Message::CloseMenu),
+                }
+            } else if y == 0 {
+                Some(Message::Action(Action::NavigationMenu))
+            } else if app.on_scrollbar(x, y) {
+                Some(Message::DragScrollbar(y))
+            } else {
+                app.link_at(x, y).map(Message::GoToLink)
+            }
+        }
+        MouseEventKind::Drag(MouseButton::Left) if app.dragging_scrollbar() => {
+            Some(Message::DragScrollbar(y))
+        }
+        MouseEventKind::Up(_) => Some(Message::ReleaseMouse),
+        MouseEventKind::Moved => Some(Message::Hover(x, y)),
+        MouseEventKind::ScrollDown => Some(Message::Scroll { up: false }),
+        MouseEventKind::ScrollUp => Some(Message::Scroll { up: true }),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_handle_event() {
+    use crate::app::Fetched;
+    use ratatui::layout::Rect;
+
+    let mut app = App::new();
+    let words = (0..200).map(|i| format!("word{i}")).collect::<Vec<_>>();
+    let html = format!("<html><body><p>{}</p></body></html>", words.join(" "));
+    app.update(Message::Fetched(Fetched::Page(Ok(html))))
+        .unwrap();
+    app.layout_page(Rect::new(0, 1, 20, 10));
+    app.dirty = false;
+
+    let send = |app: &mut App, event| {
+        if let Some(message) = handle_event(event, app) {
+            app.update(message).unwrap();
+        }
+    };
+    let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    send(&mut app, Event::Tick);
+    assert!(!app.dirty);
+
+    send(&mut app, key('3'));
+    send(&mut app, key('j'));
+    assert_eq!(app.vertical_scroll, 3);
+    assert!(app.dirty);
+
+    send(&mut app, key('/'));
+    send(&mut app, key('j'));
+    assert_eq!(app.search.as_ref().unwrap().query, "j");
+    assert_eq!(app.vertical_scroll, 3);
+
+    // Panels take the mouse events of the page they cover, and close on clicks next to them.
+    let mouse = |kind, column| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        })
+    };
+    app.resize(80, 24);
+    app.update(Message::Action(Action::Help)).unwrap();
+    send(&mut app, mouse(MouseEventKind::ScrollDown, 40));
+    send(&mut app, mouse(MouseEventKind::Down(MouseButton::Left), 40));
+    assert_eq!(app.vertical_scroll, 3);
+    assert!(app.help.is_some());
+    send(&mut app, mouse(MouseEventKind::Down(MouseButton::Left), 0));
+    assert!(app.help.is_none());
+}
+
+#[tokio::test]
+async fn test_fetched_events() {
+    use crate::app::Fetched;
+    use crate::graphics::Protocol;
+    use ratatui::layout::Rect;
+
+    let mut app = App::new();
+    app.graphics = Protocol::HalfBlocks;
+    // The image request is spawned but never runs, as the test does not yield to the runtime.
+    app.set_html("<html><body><figure><img src=\"a.png\"/></figure></body></html>");
+    let area = Rect::new(0, 1, 20, 10);
+    app.layout_page(area);
+    app.dirty = false;
+
+    let event = Event::Fetched(Fetched::Image("a.png".to_string(), None));
+    let message = handle_event(event, &app).unwrap();
+    app.update(message).unwrap();
+    assert!(app.dirty);
+    // The page is laid out again without the image.
+    assert!(app.layout_page(area));
 }
@@ -0,0 +1,511 @@
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::{action::Action, util::config_dir};
+
+/// A key with its modifiers, as written in the keymap file, e.g. `g`, `G`, `<C-d>` or
+/// `<PageDown>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is part of the character for letters and of `BackTab` for tab. Space has no
+        // shifted character, so Shift+Space keeps it.
+        let modifiers = match code {
+            KeyCode::Char(c) if c != ' ' => modifiers - KeyModifiers::SHIFT,
+            KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// The character the key types into a text field, if it types one.
+    pub fn text(self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if (self.modifiers - KeyModifiers::SHIFT).is_empty() => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Whether terminals only report the key with the kitty keyboard protocol. Without it,
+    /// Shift+Space is a space and Shift+Enter an enter.
+    pub fn needs_enhancement(self) -> bool {
+        match self.code {
+            KeyCode::Char(' ') => self.modifiers.contains(KeyModifiers::SHIFT),
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Backspace | KeyCode::Tab => self
+                .modifiers
+                .intersects(KeyModifiers::SHIFT | KeyModifiers::CONTROL),
+            _ => false,
+        }
+    }
+
+    /// Parses a sequence of keys in vim notation, e.g. `gg`, `5j` or `<C-w>j`.
+    pub fn parse_sequence(text: &str) -> Result<Vec<Key>, String> {
+        let mut keys = vec![];
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if c == '<' && rest.len() > 1 {
+                let end = rest
+                    .find('>')
+                    .ok_or_else(|| format!("'{text}' has a '<' without a closing '>'"))?;
+                keys.push(Self::parse_named(&rest[1..end])?);
+                rest = &rest[end + 1..];
+            } else {
+                keys.push(Key::new(KeyCode::Char(c), KeyModifiers::NONE));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if keys.is_empty() {
+            return Err("Empty key sequence".to_string());
+        }
+        Ok(keys)
+    }
+
+    /// Parses what is between `<` and `>`, modifiers like `C-` followed by a key name.
+    fn parse_named(name: &str) -> Result<Key, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = name;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            modifiers |= match rest.as_bytes()[0].to_ascii_uppercase() {
+                b'C' => KeyModifiers::CONTROL,
+                b'A' | b'M' => KeyModifiers::ALT,
+                b'S' => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier in '<{name}>'")),
+            };
+            rest = &rest[2..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "lt" => KeyCode::Char('<'),
+                "cr" | "enter" | "return" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "bs" | "backspace" => KeyCode::Backspace,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "del" | "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                f if f.starts_with('f') => match f[1..].parse() {
+                    Ok(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key '<{name}>'")),
+                },
+                _ => return Err(format!("Unknown key '<{name}>'")),
+            },
+        };
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char('<') => "lt".to_string(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => return write!(f, "{c}"),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "CR".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Backspace => "BS".to_string(),
+            KeyCode::BackTab => "S-Tab".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::F(n) => format!("F{n}"),
+            code => format!("{code:?}"),
+        };
+        let mut modifiers = String::new();
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "C-"),
+            (KeyModifiers::ALT, "A-"),
+            (KeyModifiers::SHIFT, "S-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                modifiers.push_str(prefix);
+            }
+        }
+        write!(f, "<{modifiers}{name}>")
+    }
+}
+
+/// Writes a key sequence back in vim notation.
+pub fn sequence_name(keys: &[Key]) -> String {
+    keys.iter().map(Key::to_string).collect()
+}
+
+/// The keymap file, `keymap.toml` in the config directory.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    /// Preset the bindings are added to, `vim` by default.
+    preset: Option<String>,
+    /// Key sequences and the name of the action they run, or `none` to unbind them.
+    #[serde(default)]
+    bindings: BTreeMap<String, String>,
+}
+
+/// Key sequences bound to actions, and the keys typed so far.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+    /// Keys of a sequence that is not complete yet.
+    pending: Vec<Key>,
+    /// Count typed before the keys, like the 5 of `5j`.
+    count: Option<usize>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::vim()
+    }
+}
+
+impl Keymap {
+    pub const PRESETS: [&'static str; 2] = ["vim", "arrows"];
+
+    fn from_bindings(bindings: &[(&str, Action)]) -> Self {
+        Self {
+            bindings: bindings
+                .iter()
+                .map(|(keys, action)| (Key::parse_sequence(keys).unwrap(), *action))
+                .collect(),
+            pending: vec![],
+            count: None,
+        }
+    }
+
+    /// Vim-like keys, with the arrow keys still working.
+    pub fn vim() -> Self {
+        Self::from_bindings(&[
+            ("q", Action::Quit),
+            ("<C-c>", Action::Quit),
+            ("j", Action::ScrollDown),
+            ("<Down>", Action::ScrollDown),
+            ("<C-e>", Action::ScrollDown),
+            ("k", Action::ScrollUp),
+            ("<Up>", Action::ScrollUp),
+            ("<C-y>", Action::ScrollUp),
+            ("<C-d>", Action::HalfPageDown),
+            ("<C-u>", Action::HalfPageUp),
+            ("<C-f>", Action::PageDown),
+            ("<PageDown>", Action::PageDown),
+            ("<C-b>", Action::PageUp),
+            ("<PageUp>", Action::PageUp),
+            ("gg", Action::Top),
+            ("<Home>", Action::Top),
+            ("G", Action::Bottom),
+            ("<End>", Action::Bottom),
+            ("<Space>", Action::RandomPage),
+            ("<S-Space>", Action::Back),
+            ("H", Action::Back),
+            ("f", Action::HintMode),
+            ("<CR>", Action::FollowLink),
+            ("<Tab>", Action::FocusNextLink),
+            ("<S-Tab>", Action::FocusPreviousLink),
+            ("l", Action::LinkList),
+            ("/", Action::Search),
+            ("n", Action::NextMatch),
+            ("N", Action::PreviousMatch),
+            ("I", Action::OpenImage),
+            ("m", Action::NavigationMenu),
+            ("T", Action::NextTheme),
+            ("+", Action::Wider),
+            ("-", Action::Narrower),
+            ("=", Action::ToggleAlign),
+            ("?", Action::Help),
+            ("<C-p>", Action::CommandPalette),
+            ("M", Action::Notifications),
+            ("a", Action::ReadLater),
+            ("S", Action::NextStrategy),
+            ("<Esc>", Action::Cancel),
+        ])
+    }
+
+    /// Arrow keys and the function and control keys of other applications.
+    pub fn arrows() -> Self {
+        Self::from_bindings(&[
+            ("<C-q>", Action::Quit),
+            ("<C-c>", Action::Quit),
+            ("<Down>", Action::ScrollDown),
+            ("<Up>", Action::ScrollUp),
+            ("<PageDown>", Action::PageDown),
+            ("<PageUp>", Action::PageUp),
+            ("<Home>", Action::Top),
+            ("<End>", Action::Bottom),
+            ("<Space>", Action::RandomPage),
+            ("<S-Space>", Action::Back),
+            ("<BS>", Action::Back),
+            ("<A-Left>", Action::Back),
+            ("f", Action::HintMode),
+            ("<CR>", Action::FollowLink),
+            ("<Tab>", Action::FocusNextLink),
+            ("<S-Tab>", Action::FocusPreviousLink),
+            ("<C-l>", Action::LinkList),
+            ("<C-f>", Action::Search),
+            ("/", Action::Search),
+            ("<F3>", Action::NextMatch),
+            ("<S-F3>", Action::PreviousMatch),
+            ("<C-o>", Action::OpenImage),
+            ("<F10>", Action::NavigationMenu),
+            ("<F7>", Action::NextTheme),
+            ("<C-Right>", Action::Wider),
+            ("<C-Left>", Action::Narrower),
+            ("<F8>", Action::ToggleAlign),
+            ("<F1>", Action::Help),
+            ("<C-p>", Action::CommandPalette),
+            ("<F2>", Action::Notifications),
+            ("<C-r>", Action::ReadLater),
+            ("<F6>", Action::NextStrategy),
+            ("<Esc>", Action::Cancel),
+        ])
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "vim" => Some(Self::vim()),
+            "arrows" => Some(Self::arrows()),
+            _ => None,
+        }
+    }
+
+    /// Path of the keymap file, `keymap.toml` in the config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("keymap.toml"))
+    }
+
+    /// Loads a keymap file.
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read keymap {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("Invalid keymap {}: {e}", path.display()))
+    }
+
+    /// Parses the contents of a keymap file: a preset and bindings added to or replacing
+    /// the ones of the preset.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: KeymapFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        let preset = file.preset.as_deref().unwrap_or("vim");
+        let mut keymap = Self::preset(preset).ok_or_else(|| {
+            format!(
+                "unknown preset '{preset}', expected one of {}",
+                Self::PRESETS.join(", ")
+            )
+        })?;
+
+        let mut seen: Vec<(&str, Vec<Key>)> = vec![];
+        for (text, name) in &file.bindings {
+            let keys = Key::parse_sequence(text)?;
+            if let Some((other, _)) = seen.iter().find(|(_, k)| *k == keys) {
+                return Err(format!("'{other}' and '{text}' are the same keys"));
+            }
+            keymap.bindings.retain(|(k, _)| *k != keys);
+            if name != "none" {
+                let action = Action::from_name(name)
+                    .ok_or_else(|| format!("unknown action '{name}' for '{text}'"))?;
+                keymap.bindings.push((keys.clone(), action));
+            }
+            seen.push((text, keys));
+        }
+
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    /// Checks that every sequence can be typed: none starts with a count, and none is the
+    /// start of another one, which would run before the longer one is complete.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = vec![];
+        for (i, (keys, action)) in self.bindings.iter().enumerate() {
+            if Self::count_digit(keys[0], None).is_some() {
+                errors.push(format!(
+                    "'{}' ({}) starts with a digit, which is reserved for counts",
+                    sequence_name(keys),
+                    action.name()
+                ));
+            }
+            for (j, (other, other_action)) in self.bindings.iter().enumerate() {
+                if i != j && other.starts_with(keys) && (keys != other || i < j) {
+                    errors.push(format!(
+                        "'{}' ({}) conflicts with '{}' ({}), unbind one of them with \"none\"",
+                        sequence_name(keys),
+                        action.name(),
+                        sequence_name(other),
+                        other_action.name()
+                    ));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Drops the bindings terminals without the kitty keyboard protocol cannot report, and
+    /// binds the actions they leave without keys to their keys in the vim preset, where
+    /// those are free. Returns the dropped bindings.
+    pub fn fall_back_to_legacy(&mut self) -> Vec<String> {
+        let (dropped, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.bindings)
+            .into_iter()
+            .partition(|(keys, _)| keys.iter().any(|k| k.needs_enhancement()));
+        self.bindings = kept;
+
+        for (keys, action) in Self::vim().bindings {
+            let lost = dropped.iter().any(|(_, a)| *a == action)
+                && !self.bindings.iter().any(|(_, a)| *a == action);
+            let free = !self
+                .bindings
+                .iter()
+                .any(|(k, _)| k.starts_with(&keys) || keys.starts_with(k));
+            if lost && free && !keys.iter().any(|k| k.needs_enhancement()) {
+                self.bindings.push((keys, action));
+            }
+        }
+        dropped
+            .iter()
+            .map(|(keys, action)| format!("'{}' ({})", sequence_name(keys), action.name()))
+            .collect()
+    }
+
+    /// Every binding, in the order of the preset followed by the keymap file.
+    pub fn bindings(&self) -> &[(Vec<Key>, Action)] {
+        &self.bindings
+    }
+
+    /// The count and keys typed so far of a sequence that is not complete yet.
+    pub fn pending(&self) -> String {
+        let count = self.count.map(|c| c.to_string()).unwrap_or_default();
+        format!("{count}{}", sequence_name(&self.pending))
+    }
+
+    /// Forgets the keys typed so far.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// Takes a typed key, returning the action it completes with its count.
+    pub fn feed(&mut self, key: Key) -> Option<(Action, usize)> {
+        // Modifiers pressed on their own, reported with the kitty keyboard protocol, only
+        // modify the next key.
+        if matches!(key.code, KeyCode::Modifier(_)) {
+            return None;
+        }
+        if self.pending.is_empty() {
+            if let Some(digit) = Self::count_digit(key, self.count) {
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10) + digit);
+                return None;
+            }
+        }
+
+        self.pending.push(key);
+        let mut candidates = self
+            .bindings
+            .iter()
+            .filter(|(keys, _)| keys.starts_with(&self.pending));
+        match candidates.next() {
+            Some((keys, action)) if *keys == self.pending => {
+                let found = (*action, self.count.unwrap_or(1));
+                self.clear();
+                Some(found)
+            }
+            Some(_) => None,
+            None => {
+                self.clear();
+                None
+            }
+        }
+    }
+
+    /// The value of a count digit. Zero only continues a count.
+    fn count_digit(key: Key, count: Option<usize>) -> Option<usize> {
+        match key.code {
+            KeyCode::Char(c @ '0'..='9') if key.modifiers.is_empty() => {
+                let digit = c.to_digit(10).unwrap() as usize;
+                (digit > 0 || count.is_some()).then_some(digit)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_keymap() {
+    assert!(Keymap::vim().validate().is_ok());
+    assert!(Keymap::arrows().validate().is_ok());
+
+    let mut keymap =
+        Keymap::parse("preset = \"vim\"\n[bindings]\n\"<C-n>\" = \"scroll_down\"").unwrap();
+    let key = |c| Key::new(KeyCode::Char(c), KeyModifiers::NONE);
+    assert_eq!(keymap.feed(key('1')), None);
+    assert_eq!(keymap.feed(key('2')), None);
+    assert_eq!(keymap.feed(key('j')), Some((Action::ScrollDown, 12)));
+    assert_eq!(keymap.feed(key('g')), None);
+    assert_eq!(keymap.pending(), "g");
+    assert_eq!(keymap.feed(key('g')), Some((Action::Top, 1)));
+    assert_eq!(
+        keymap.feed(Key::new(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+        Some((Action::ScrollDown, 1))
+    );
+    assert_eq!(
+        keymap.feed(Key::new(KeyCode::Char('N'), KeyModifiers::SHIFT)),
+        Some((Action::PreviousMatch, 1))
+    );
+    let shift = Key::new(
+        KeyCode::Modifier(crossterm::event::ModifierKeyCode::LeftShift),
+        KeyModifiers::SHIFT,
+    );
+    assert_eq!(keymap.feed(key('3')), None);
+    assert_eq!(keymap.feed(shift), None);
+    assert_eq!(
+        keymap.feed(Key::new(KeyCode::Char('N'), KeyModifiers::SHIFT)),
+        Some((Action::PreviousMatch, 3))
+    );
+    assert_eq!(keymap.feed(key('g')), None);
+    assert_eq!(keymap.feed(shift), None);
+    assert_eq!(keymap.feed(key('g')), Some((Action::Top, 1)));
+
+    assert_eq!(
+        Key::parse_sequence("<S-Tab><C-w>x<Space>").map(|k| sequence_name(&k)),
+        Ok("<S-Tab><C-w>x<Space>".to_string())
+    );
+    assert!(Keymap::parse("[bindings]\ng = \"top\"").is_err());
+    assert!(Keymap::parse("[bindings]\n\" \" = \"top\"\n\"<Space>\" = \"back\"").is_err());
+    assert!(Keymap::parse("[bindings]\n\"3\" = \"top\"").is_err());
+    assert!(Keymap::parse("[bindings]\nx = \"fly\"").is_err());
+    assert!(Keymap::parse("[bindings]\ngg = \"none\"\ng = \"top\"").is_ok());
+
+    let shift_space = Key::new(KeyCode::Char(' '), KeyModifiers::SHIFT);
+    assert_eq!(keymap.feed(shift_space), Some((Action::Back, 1)));
+    let mut keymap = Keymap::parse(
+        "preset = \"arrows\"\n[bindings]\n\"<BS>\" = \"none\"\n\"<A-Left>\" = \"none\"",
+    )
+    .unwrap();
+    assert_eq!(keymap.fall_back_to_legacy(), vec!["'<S-Space>' (back)"]);
+    assert_eq!(keymap.feed(shift_space), None);
+    assert_eq!(keymap.feed(key('H')), Some((Action::Back, 1)));
+}
@@ -0,0 +1,376 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Part of a wrapped row covered by a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkArea {
+    pub row: usize,
+    pub x: u16,
+    pub width: u16,
+}
+
+impl LinkArea {
+    pub fn contains(&self, row: usize, x: u16) -> bool {
+        self.row == row && (self.x..self.x + self.width).contains(&x)
+    }
+}
+
+/// A position in the page that does not depend on the width it is wrapped to: byte `offset`
+/// of line `line` of paragraph `paragraph`. Paragraphs are a single line of text followed
+/// by an empty one, or the lines of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Anchor {
+    pub paragraph: usize,
+    pub line: usize,
+    pub offset: usize,
+}
+
+/// Page text wrapped to a fixed width, remembering where every line and link ended up.
+#[derive(Debug, Default)]
+pub struct Layout {
+    pub width: u16,
+    /// Wrapped rows, rendered as is without wrapping again.
+    pub rows: Vec<Line<'static>>,
+    /// Index of the first row of every source line.
+    pub line_starts: Vec<usize>,
+    /// Byte offset in its source line at which every row starts.
+    pub row_offsets: Vec<usize>,
+    /// Areas covered by every link, indexed by link number. Links that are wrapped
+    /// have more than one area.
+    pub link_areas: Vec<Vec<LinkArea>>,
+}
+
+impl Layout {
+    /// Wraps `lines` at word boundaries to fit in `width` columns. `span_links` holds, for
+    /// every line, the link number each of its spans belongs to.
+    pub fn wrap(lines: &[Line], span_links: &[Vec<Option<usize>>], width: u16) -> Self {
+        let mut layout = Layout {
+            width,
+            ..Default::default()
+        };
+        if width == 0 {
+            layout.line_starts = vec![0; lines.len()];
+            return layout;
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            layout.line_starts.push(layout.rows.len());
+            let links = span_links.get(i).map(Vec::as_slice).unwrap_or(&[]);
+            layout.wrap_line(line, links);
+        }
+        layout
+    }
+
+    /// Returns the row that byte `offset` of source line `line` was wrapped to.
+    pub fn row_of(&self, line: usize, offset: usize) -> usize {
+        let start = self.line_starts.get(line).copied().unwrap_or(0);
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.rows.len());
+        (start..end)
+            .take_while(|&row| row == start || self.row_offsets[row] <= offset)
+            .last()
+            .unwrap_or(start)
+    }
+
+    /// Returns the position at the start of row `row`, given the first source line of every
+    /// paragraph.
+    pub fn anchor_at(&self, row: usize, paragraph_starts: &[usize]) -> Anchor {
+        let line = self.line_of(row);
+        let paragraph = paragraph_starts
+            .partition_point(|&start| start <= line)
+            .saturating_sub(1);
+        Anchor {
+            paragraph,
+            line: line - paragraph_starts.get(paragraph).copied().unwrap_or(0),
+            offset: self.row_offsets.get(row).copied().unwrap_or(0),
+        }
+    }
+
+    /// Returns the row `anchor` was wrapped to, given the first source line of every
+    /// paragraph.
+    pub fn row_of_anchor(&self, anchor: Anchor, paragraph_starts: &[usize]) -> usize {
+        let Some(start) = paragraph_starts.get(anchor.paragraph) else {
+            return 0;
+        };
+        let line = (start + anchor.line).min(self.line_starts.len().saturating_sub(1));
+        self.row_of(line, anchor.offset)
+    }
+
+    /// Returns the source line that row `row` belongs to.
+    pub fn line_of(&self, row: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= row)
+            .saturating_sub(1)
+    }
+
+    /// Patches `style` onto the byte range `start..end` of source line `line`, on every row
+    /// the range was wrapped to.
+    pub fn highlight(&mut self, line: usize, start: usize, end: usize, style: Style) {
+        if self.rows.is_empty() || start >= end {
+            return;
+        }
+        for row in self.row_of(line, start)..=self.row_of(line, end - 1) {
+            // Rows hold a contiguous part of their source line, starting at its offset.
+            let row_start = self.row_offsets[row];
+            let row_length = self.rows[row]
+                .spans
+                .iter()
+                .map(|s| s.content.len())
+                .sum::<usize>();
+            let from = start.max(row_start) - row_start;
+            let to = end.min(row_start + row_length).saturating_sub(row_start);
+            if from < to {
+                let row_line = std::mem::take(&mut self.rows[row]);
+                self.rows[row] = highlight(row_line, from, to, style);
+            }
+        }
+    }
+
+    /// Returns the link covering the cell at `x` of wrapped row `row`.
+    pub fn link_at(&self, row: usize, x: u16) -> Option<usize> {
+        self.link_areas
+            .iter()
+            .position(|areas| areas.iter().any(|a| a.contains(row, x)))
+    }
+
+    fn wrap_line(&mut self, line: &Line, links: &[Option<usize>]) {
+        // Every grapheme with the span it is from and its byte offset in the line.
+        let mut graphemes: Vec<(&str, usize, usize)> = vec![];
+        let mut offset = 0;
+        for (i, span) in line.spans.iter().enumerate() {
+            for (o, g) in span.content.grapheme_indices(true) {
+                graphemes.push((g, i, offset + o));
+            }
+            offset += span.content.len();
+        }
+
+        // Split into alternating runs of whitespace and words.
+        let mut tokens: Vec<&[(&str, usize, usize)]> = vec![];
+        let mut start = 0;
+        for i in 1..=graphemes.len() {
+            if i == graphemes.len() || is_space(graphemes[i].0) != is_space(graphemes[start].0) {
+                tokens.push(&graphemes[start..i]);
+                start = i;
+            }
+        }
+
+        let width = self.width as usize;
+        let mut rows: Vec<Vec<(&str, usize, usize)>> = vec![vec![]];
+        let mut column = 0;
+        for token in tokens {
+            let token_width = token.iter().map(|(g, _, _)| g.width()).sum::<usize>();
+            if is_space(token[0].0) {
+                if column + token_width > width {
+                    rows.push(vec![]);
+                    column = 0;
+                } else if column > 0 || rows.len() == 1 {
+                    // Whitespace at the start of a wrapped row is dropped.
+                    rows.last_mut().unwrap().extend_from_slice(token);
+                    column += token_width;
+                }
+                continue;
+            }
+
+            if column + token_width > width && column > 0 {
+                rows.push(vec![]);
+                column = 0;
+            }
+            for &grapheme in token {
+                // Words longer than a row are broken anywhere.
+                if column + grapheme.0.width() > width && column > 0 {
+                    rows.push(vec![]);
+                    column = 0;
+                }
+                rows.last_mut().unwrap().push(grapheme);
+                column += grapheme.0.width();
+            }
+        }
+
+        let mut row_offset = 0;
+        for row in rows {
+            row_offset = row.first().map_or(row_offset, |(_, _, o)| *o);
+            self.row_offsets.push(row_offset);
+            self.push_row(line, links, &row);
+        }
+    }
+
+    /// Builds a row out of graphemes, merging runs from the same span.
+    fn push_row(
+        &mut self,
+        line: &Line,
+        links: &[Option<usize>],
+        graphemes: &[(&str, usize, usize)],
+    ) {
+        let row = self.rows.len();
+        let mut spans: Vec<Span<'static>> = vec![];
+        let mut x = 0;
+        let mut i = 0;
+        while i < graphemes.len() {
+            let span = graphemes[i].1;
+            let run = graphemes[i..]
+                .iter()
+                .take_while(|(_, s, _)| *s == span)
+                .count();
+            let content = graphemes[i..i + run]
+                .iter()
+                .map(|(g, _, _)| *g)
+                .collect::<String>();
+            let width = content.width() as u16;
+
+            if let Some(Some(link)) = links.get(span) {
+                if self.link_areas.len() <= *link {
+                    self.link_areas.resize(*link + 1, vec![]);
+                }
+                let areas = &mut self.link_areas[*link];
+                match areas.last_mut() {
+                    // The link text and its label are separate spans.
+                    Some(area) if area.row == row && area.x + area.width == x => {
+                        area.width += width
+                    }
+                    _ => areas.push(LinkArea { row, x, width }),
+                }
+            }
+
+            spans.push(Span::styled(content, line.spans[span].style));
+            x += width;
+            i += run;
+        }
+        self.rows.push(Line::from(spans));
+    }
+}
+
+/// Where the reading column goes when the screen is wider than it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnAlign {
+    Left,
+    #[default]
+    Center,
+}
+
+impl ColumnAlign {
+    pub fn toggle(self) -> Self {
+        match self {
+            ColumnAlign::Left => ColumnAlign::Center,
+            ColumnAlign::Center => ColumnAlign::Left,
+        }
+    }
+}
+
+/// The part of `area` text is wrapped in, at most `max_width` columns wide. A `max_width`
+/// of 0 takes the whole width.
+pub fn column(area: Rect, max_width: u16, align: ColumnAlign) -> Rect {
+    if max_width == 0 || max_width >= area.width {
+        return area;
+    }
+    let margin = match align {
+        ColumnAlign::Left => 0,
+        ColumnAlign::Center => (area.width - max_width) / 2,
+    };
+    Rect {
+        x: area.x + margin,
+        width: max_width,
+        ..area
+    }
+}
+
+/// Patches `style` onto the byte range `start..end` of a line, splitting spans where needed.
+pub fn highlight<'a>(line: Line<'a>, start: usize, end: usize, style: Style) -> Line<'a> {
+    let mut spans = vec![];
+    let mut offset = 0;
+    for span in line.spans {
+        let span_start = offset;
+        let span_end = offset + span.content.len();
+        offset = span_end;
+        if span_end <= start || span_start >= end {
+            spans.push(span);
+            continue;
+        }
+
+        let content = span.content.as_ref();
+        let from = start.saturating_sub(span_start);
+        let to = (end - span_start).min(content.len());
+        if from > 0 {
+            spans.push(Span::styled(content[..from].to_string(), span.style));
+        }
+        spans.push(Span::styled(
+            content[from..to].to_string(),
+            span.style.patch(style),
+        ));
+        if to < content.len() {
+            spans.push(Span::styled(content[to..].to_string(), span.style));
+        }
+    }
+    Line {
+        spans,
+        alignment: line.alignment,
+    }
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[test]
+fn test_wrap() {
+    let lines = vec![Line::from(vec![
+        Span::raw("The quick "),
+        Span::raw("brown fox"),
+        Span::raw(" jumps over"),
+    ])];
+    let span_links = vec![vec![None, Some(0), None]];
+    let layout = Layout::wrap(&lines, &span_links, 12);
+
+    let rows = layout
+        .rows
+        .iter()
+        .map(|r| {
+            r.spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec!["The quick ", "brown fox ", "jumps over"]);
+    assert_eq!(
+        layout.link_areas[0],
+        vec![LinkArea {
+            row: 1,
+            x: 0,
+            width: 9
+        }]
+    );
+    assert_eq!(layout.link_at(1, 4), Some(0));
+    assert_eq!(layout.row_of(0, "The quick brown fox jumps".len()), 2);
+
+    let line = highlight(
+        Line::from(vec![Span::raw("The "), Span::raw("Cat sat")]),
+        4,
+        7,
+        Style::default(),
+    );
+    let contents = line
+        .spans
+        .iter()
+        .map(|s| s.content.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(contents, vec!["The ", "Cat", " sat"]);
+    assert_eq!(layout.link_at(0, 4), None);
+
+    let area = Rect::new(0, 1, 100, 20);
+    assert_eq!(
+        column(area, 60, ColumnAlign::Center),
+        Rect::new(20, 1, 60, 20)
+    );
+    assert_eq!(column(area, 60, ColumnAlign::Left), Rect::new(0, 1, 60, 20));
+    assert_eq!(column(area, 0, ColumnAlign::Center), area);
+}
@@ -1,7 +1,22 @@
+pub mod action;
 pub mod app;
+pub mod config;
 pub mod event;
+pub mod graphics;
 pub mod handler;
+pub mod keymap;
+pub mod layout;
+pub mod links;
+pub mod metrics;
+pub mod notifications;
+pub mod palette;
 mod parser;
+pub mod recommend;
+pub mod search;
+pub mod session;
+pub mod status;
+pub mod strategy;
+pub mod theme;
 pub mod tui;
 pub mod ui;
 pub mod util;
@@ -0,0 +1,165 @@
+use crate::{
+    parser::Link,
+    util::{fuzzy_score, percent_decode},
+};
+
+/// Order of the entries in the link list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkOrder {
+    /// As they appear on the page.
+    Document,
+    /// Targets that are linked most often first.
+    Frequency,
+}
+
+/// State of the panel listing every link on the page.
+#[derive(Debug)]
+pub struct LinkList {
+    pub query: String,
+    pub order: LinkOrder,
+    /// Selected entry, as an index into [`LinkList::entries`].
+    pub selected: usize,
+}
+
+/// A link as shown in the link list.
+pub struct LinkEntry<'a> {
+    /// Number of the link on the page.
+    pub index: usize,
+    pub link: &'a Link,
+    /// Title of the page the link points to.
+    pub title: String,
+    /// The sentence the link is in.
+    pub context: &'a str,
+}
+
+impl LinkList {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            order: LinkOrder::Document,
+            selected: 0,
+        }
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.order = match self.order {
+            LinkOrder::Document => LinkOrder::Frequency,
+            LinkOrder::Frequency => LinkOrder::Document,
+        };
+        self.selected = 0;
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Moves the selection by `delta` entries, staying within `count` entries.
+    pub fn move_selection(&mut self, delta: isize, count: usize) {
+        let selected = self.selected as isize + delta;
+        self.selected = selected.clamp(0, count.saturating_sub(1) as isize) as usize;
+    }
+
+    /// Returns the links matching the query, the best matches first, then in the selected
+    /// order. `contexts` holds the sentence of every link and `counts` how many links point
+    /// to the same page.
+    pub fn entries<'a>(
+        &self,
+        links: &'a [Link],
+        contexts: &'a [String],
+        counts: &[usize],
+    ) -> Vec<LinkEntry<'a>> {
+        let mut entries = links
+            .iter()
+            .zip(contexts)
+            .enumerate()
+            .filter_map(|(index, (link, context))| {
+                let title = link_title(&link.link);
+                let score =
+                    fuzzy_score(&self.query, &link.text).max(fuzzy_score(&self.query, &title))?;
+                let count = match self.order {
+                    LinkOrder::Document => 0,
+                    LinkOrder::Frequency => counts.get(index).copied().unwrap_or(1),
+                };
+                let entry = LinkEntry {
+                    index,
+                    link,
+                    title,
+                    context,
+                };
+                Some((score, count, entry))
+            })
+            .collect::<Vec<_>>();
+        // The sort is stable, so equal entries, like links to the same page, stay in
+        // document order.
+        entries.sort_by_key(|(score, count, _)| std::cmp::Reverse((*score, *count)));
+        entries.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+}
+
+impl Default for LinkList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turns a link like `./New_York_City#History` into the title `New York City`.
+pub fn link_title(href: &str) -> String {
+    let page = href.trim_start_matches("./");
+    let page = page.split('#').next().unwrap_or(page);
+    percent_decode(page).replace('_', " ")
+}
+
+/// Returns the sentence of `text` that contains the byte range `start..end`.
+pub fn sentence_around(text: &str, start: usize, end: usize) -> &str {
+    let sentence_start = text[..start].rfind(". ").map_or(0, |i| i + 2);
+    let sentence_end = text[end..].find(". ").map_or(text.len(), |i| end + i + 1);
+    text[sentence_start..sentence_end].trim()
+}
+
+#[test]
+fn test_sentence_around() {
+    let text = "First sentence. The link is here. Last one.";
+    let start = text.find("link").unwrap();
+    assert_eq!(sentence_around(text, start, start + 4), "The link is here.");
+    assert_eq!(link_title("./Caf%C3%A9_au_lait#History"), "Café au lait");
+}
+
+#[test]
+fn test_link_list_entries() {
+    let links = [
+        ("./Paris", "Paris"),
+        ("./Lyon", "Lyon"),
+        ("./Paris_Metro", "metro"),
+        ("./Lyon", "the city"),
+        ("./Italy", "Italy"),
+    ]
+    .map(|(link, text)| Link {
+        link: link.to_string(),
+        text: text.to_string(),
+    });
+    let contexts = vec![String::new(); links.len()];
+    let counts = [1, 2, 1, 2, 1];
+    let order = |list: &LinkList| {
+        list.entries(&links, &contexts, &counts)
+            .iter()
+            .map(|e| e.index)
+            .collect::<Vec<_>>()
+    };
+
+    let mut list = LinkList::new();
+    assert_eq!(order(&list), vec![0, 1, 2, 3, 4]);
+    list.toggle_order();
+    assert_eq!(order(&list), vec![1, 3, 0, 2, 4]);
+    // The closest matches come first, then the most linked pages.
+    list.push('i');
+    assert_eq!(order(&list), vec![4, 3, 0, 2]);
+    list.toggle_order();
+    list.push('t');
+    assert_eq!(order(&list), vec![4, 3, 2]);
+}
@@ -1,11 +1,18 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use clikipedia_tui::app::{App, AppResult};
-use clikipedia_tui::event::{Event, EventHandler};
-use clikipedia_tui::handler::{handle_key_events, handle_mouse_events};
+use clikipedia_tui::config::Config;
+use clikipedia_tui::event::EventHandler;
+use clikipedia_tui::handler::handle_event;
+use clikipedia_tui::keymap::Keymap;
+use clikipedia_tui::layout::ColumnAlign;
+use clikipedia_tui::notifications::Severity;
+use clikipedia_tui::session::{Session, SessionPicker};
+use clikipedia_tui::strategy;
 use clikipedia_tui::tui::Tui;
+use clikipedia_tui::util::set_log_file;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
-use std::{fs, io};
+use std::{fs, io, path::PathBuf};
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
@@ -24,40 +31,238 @@ async fn main() -> AppResult<()> {
                 .help("Title of a page to look up")
                 .conflicts_with("html"),
         )
+        .arg(
+            Arg::new("images")
+                .long("images")
+                .value_name("PROTOCOL")
+                .value_parser(["auto", "none", "halfblocks", "sixel", "kitty"])
+                .help("How to draw images in the terminal"),
+        )
+        .arg(
+            Arg::new("hint-alphabet")
+                .long("hint-alphabet")
+                .value_name("CHARS")
+                .help("Characters used for link hint labels"),
+        )
+        .arg(
+            Arg::new("keymap")
+                .long("keymap")
+                .value_name("FILE")
+                .help("Key bindings file, instead of keymap.toml in the config directory"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Config file, instead of config.toml in the config directory"),
+        )
+        .arg(
+            Arg::new("site")
+                .long("site")
+                .value_name("HOST")
+                .help("Wikipedia to read, e.g. de.wikipedia.org"),
+        )
+        .arg(
+            Arg::new("user-email")
+                .long("user-email")
+                .value_name("EMAIL")
+                .help("E-mail address sent in the user agent"),
+        )
+        .arg(
+            Arg::new("tick-rate")
+                .long("tick-rate")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64))
+                .help("Milliseconds between ticks"),
+        )
+        .arg(
+            Arg::new("html-dir")
+                .long("html-dir")
+                .value_name("DIR")
+                .help("Directory visited pages are saved to"),
+        )
+        .arg(
+            Arg::new("session-dir")
+                .long("session-dir")
+                .value_name("DIR")
+                .help("Directory sessions are saved to"),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("FILE")
+                .help("File debug logs are written to"),
+        )
+        .arg(
+            Arg::new("max-width")
+                .long("max-width")
+                .value_name("COLUMNS")
+                .value_parser(clap::value_parser!(u16))
+                .help("Maximum width of the text, 0 for the whole terminal"),
+        )
+        .arg(
+            Arg::new("strategy")
+                .long("strategy")
+                .value_parser(strategy::names())
+                .help("How next pages are picked"),
+        )
+        .arg(
+            Arg::new("exploration")
+                .long("exploration")
+                .value_name("0-1")
+                .value_parser(clap::value_parser!(f64))
+                .help("How far next pages stray from the pages read, 1 for random pages"),
+        )
+        .arg(
+            Arg::new("align")
+                .long("align")
+                .value_parser(["left", "center"])
+                .help("Alignment of the text on wide terminals"),
+        )
+        .arg(
+            Arg::new("print-config")
+                .long("print-config")
+                .action(ArgAction::SetTrue)
+                .help("Prints the effective configuration and exits"),
+        )
         .get_matches();
 
-    let mut app = App::new();
+    let config_path = match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Config::default_path().filter(|path| path.exists()),
+    };
+    let mut config = match config_path {
+        Some(path) => match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("{e}");
+                return Ok(());
+            }
+        },
+        None => Config::default(),
+    };
+    for (flag, setting) in [
+        ("site", &mut config.site),
+        ("user-email", &mut config.user_email),
+        ("images", &mut config.images),
+        ("hint-alphabet", &mut config.hint_alphabet),
+        ("strategy", &mut config.strategy),
+    ] {
+        if let Some(value) = matches.get_one::<String>(flag) {
+            *setting = value.clone();
+        }
+    }
+    for (flag, setting) in [
+        ("html-dir", &mut config.html_dir),
+        ("session-dir", &mut config.session_dir),
+        ("log-file", &mut config.log_file),
+    ] {
+        if let Some(value) = matches.get_one::<String>(flag) {
+            *setting = PathBuf::from(value);
+        }
+    }
+    if let Some(tick_rate) = matches.get_one::<u64>("tick-rate") {
+        config.tick_rate = *tick_rate;
+    }
+    if let Some(max_width) = matches.get_one::<u16>("max-width") {
+        config.max_width = *max_width;
+    }
+    if let Some(exploration) = matches.get_one::<f64>("exploration") {
+        config.exploration = *exploration;
+    }
+    if let Some(align) = matches.get_one::<String>("align") {
+        config.align = match align.as_str() {
+            "left" => ColumnAlign::Left,
+            _ => ColumnAlign::Center,
+        };
+    }
 
-    if let Some(html_path) = matches.get_one::<String>("html") {
-        if let Ok(html) = fs::read_to_string(html_path) {
-            app.set_html(&html);
-        } else {
-            println!("Cannot read file {}", html_path);
-            return Ok(());
+    if matches.get_flag("print-config") {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    if let Err(e) = config.validate() {
+        println!("{e}");
+        return Ok(());
+    }
+
+    set_log_file(config.log_file.clone());
+    let tick_rate = config.tick_rate;
+    let mut app = App::with_config(config);
+
+    let keymap_path = match matches.get_one::<String>("keymap") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Keymap::default_path().filter(|path| path.exists()),
+    };
+    if let Some(path) = keymap_path {
+        match Keymap::load(&path) {
+            Ok(keymap) => app.keymap = keymap,
+            Err(e) => {
+                println!("{e}");
+                return Ok(());
+            }
         }
-    } else if let Some(page_str) = matches.get_one::<String>("page") {
-        println!("Searching for page: {}", page_str);
-        unimplemented!();
     }
 
+    // Sessions are picked on startup, unless a page to open is given.
+    let (sessions, errors) =
+        Session::load_all(&app.config.session_dir).unwrap_or_else(|e| (vec![], vec![e]));
+    for error in errors {
+        app.notify(Severity::Warning, error);
+    }
+    if sessions.is_empty() || matches.contains_id("html") || matches.contains_id("page") {
+        app.session = Some(Session::new());
+    } else {
+        app.session_picker = Some(SessionPicker::new(sessions));
+    }
+
+    // The file is read before the terminal is set up to report errors, but only shown once
+    // the event sender is installed, or the images it fetches are never received.
+    let html = match matches.get_one::<String>("html") {
+        Some(html_path) => match fs::read_to_string(html_path) {
+            Ok(html) => Some(html),
+            Err(_) => {
+                println!("Cannot read file {}", html_path);
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
-    let events = EventHandler::new(250);
-    let mut tui = Tui::new(terminal, events);
-    tui.init()?;
-    app.init().await?;
+    let mut tui = Tui::new(terminal);
+    if !tui.init()? {
+        for dropped in app.keymap.fall_back_to_legacy() {
+            app.notify(
+                Severity::Warning,
+                format!("{dropped} needs a terminal with the kitty keyboard protocol"),
+            );
+        }
+    }
+    let mut events = EventHandler::new(tick_rate);
+    app.events = events.sender();
+    if let Some(html) = html {
+        app.set_html(&html);
+    }
+    if let Some(page) = matches.get_one::<String>("page") {
+        app.open_page(&page.replace(' ', "_"));
+    }
+    app.init();
 
     while app.running {
-        tui.draw(&mut app)?;
+        if app.dirty {
+            tui.draw(&mut app)?;
+            app.dirty = false;
+        }
 
-        match tui.events.next().await? {
-            Event::Tick => app.tick(),
-            Event::Key(key_event) => handle_key_events(key_event, &mut app).await?,
-            Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app)?,
-            Event::Resize(width, height) => app.resize(width, height),
+        let event = events.next().await?;
+        if let Some(message) = handle_event(event, &app) {
+            app.update(message)?;
         }
     }
 
+    app.save_session();
     tui.exit()?;
     Ok(())
 }
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Time without input after which the reader is taken to be away.
+pub const IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Interest shown in a page, from what the reader did on it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageMetrics {
+    /// Seconds spent reading the page, not counting idle time or time in another window.
+    pub dwell: f64,
+    /// Furthest the page was read, in percent.
+    pub depth: usize,
+    /// Links followed from the page, in order.
+    pub followed: Vec<String>,
+}
+
+/// Reading time after which a page counts as fully read.
+const READ_SECS: f64 = 180.0;
+/// Followed links after which a page counts as fully explored.
+const FOLLOWED_LINKS: f64 = 3.0;
+
+impl PageMetrics {
+    /// How interesting the page was to the reader, from 0 to 1. Reading time counts most,
+    /// then how far it was read, then the links followed from it.
+    pub fn interest(&self) -> f64 {
+        let dwell = (self.dwell / READ_SECS).min(1.0);
+        let depth = (self.depth as f64 / 100.0).min(1.0);
+        let followed = (self.followed.len() as f64 / FOLLOWED_LINKS).min(1.0);
+        0.5 * dwell + 0.3 * depth + 0.2 * followed
+    }
+}
+
+/// Measures reading time, paused while the terminal is unfocused or no input came for
+/// [`IDLE_AFTER`].
+#[derive(Debug)]
+pub struct DwellTimer {
+    focused: bool,
+    last_input: Instant,
+    /// Time up to which reading time was counted.
+    counted_until: Instant,
+    /// Reading time counted since it was last taken.
+    counted: Duration,
+}
+
+impl DwellTimer {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            focused: true,
+            last_input: now,
+            counted_until: now,
+            counted: Duration::ZERO,
+        }
+    }
+
+    /// Counts the time up to `now`, as far as it was spent reading.
+    fn count(&mut self, now: Instant) {
+        let until = now.min(self.last_input + IDLE_AFTER);
+        if self.focused && until > self.counted_until {
+            self.counted += until - self.counted_until;
+        }
+        self.counted_until = self.counted_until.max(now);
+    }
+
+    /// Records input, which ends being idle.
+    pub fn input(&mut self, now: Instant) {
+        self.count(now);
+        self.last_input = now;
+    }
+
+    /// Pauses the timer while the terminal is unfocused.
+    pub fn focus(&mut self, focused: bool, now: Instant) {
+        self.count(now);
+        self.focused = focused;
+        if focused {
+            self.last_input = now;
+        }
+    }
+
+    /// Returns the reading time since it was last taken.
+    pub fn take(&mut self, now: Instant) -> Duration {
+        self.count(now);
+        std::mem::take(&mut self.counted)
+    }
+}
+
+#[test]
+fn test_dwell_timer() {
+    let start = Instant::now();
+    let at = |secs| start + Duration::from_secs(secs);
+    let mut timer = DwellTimer::new(start);
+    assert_eq!(timer.take(at(10)), Duration::from_secs(10));
+
+    // Idle after a minute without input, until the next key.
+    assert_eq!(timer.take(at(100)), Duration::from_secs(50));
+    timer.input(at(200));
+    assert_eq!(timer.take(at(205)), Duration::from_secs(5));
+
+    // Paused in another window.
+    timer.focus(false, at(210));
+    assert_eq!(timer.take(at(300)), Duration::from_secs(5));
+    timer.focus(true, at(400));
+    assert_eq!(timer.take(at(402)), Duration::from_secs(2));
+}
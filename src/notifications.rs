@@ -0,0 +1,122 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::wikipedia::RateLimited;
+
+/// Most notifications shown at once, older ones only stay in the history.
+const MAX_SHOWN: usize = 3;
+/// Most notifications kept in the history.
+const MAX_HISTORY: usize = 100;
+
+/// How bad a notification is, which sets its style and how long it is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn duration(self) -> Duration {
+        Duration::from_secs(match self {
+            Severity::Info => 3,
+            Severity::Warning => 5,
+            Severity::Error => 8,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    /// When it was made, shown in the history.
+    pub time: DateTime<Local>,
+    created: Instant,
+}
+
+impl Notification {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            time: Local::now(),
+            created: Instant::now(),
+        }
+    }
+
+    /// A request that failed, a warning if it was only rate limited.
+    pub fn failed(what: &str, error: &(dyn Error + 'static)) -> Self {
+        let severity = if error.is::<RateLimited>() {
+            Severity::Warning
+        } else {
+            Severity::Error
+        };
+        Self::new(severity, format!("{what}: {error}"))
+    }
+}
+
+/// Notifications on screen, and the ones that came before them.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    shown: Vec<Notification>,
+    history: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, notification: Notification) {
+        self.history.push(notification.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.shown.push(notification);
+        if self.shown.len() > MAX_SHOWN {
+            self.shown.remove(0);
+        }
+    }
+
+    /// Hides the notifications that were shown long enough, returning whether any were.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let count = self.shown.len();
+        self.shown
+            .retain(|n| now.duration_since(n.created) < n.severity.duration());
+        self.shown.len() != count
+    }
+
+    /// Hides every notification.
+    pub fn dismiss(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Notifications on screen, oldest first.
+    pub fn shown(&self) -> &[Notification] {
+        &self.shown
+    }
+
+    /// Every notification, oldest first.
+    pub fn history(&self) -> &[Notification] {
+        &self.history
+    }
+}
+
+#[test]
+fn test_notifications() {
+    let mut notifications = Notifications::default();
+    for i in 0..4 {
+        notifications.push(Notification::new(Severity::Info, format!("{i}")));
+    }
+    notifications.push(Notification::failed("Cannot load page", &RateLimited));
+    assert_eq!(notifications.shown().len(), MAX_SHOWN);
+    assert_eq!(notifications.history().len(), 5);
+    assert_eq!(notifications.shown()[2].severity, Severity::Warning);
+
+    assert!(!notifications.expire(Instant::now()));
+    assert!(notifications.expire(Instant::now() + Duration::from_secs(4)));
+    assert_eq!(notifications.shown().len(), 1);
+    assert!(notifications.expire(Instant::now() + Duration::from_secs(6)));
+    assert_eq!(notifications.history().len(), 5);
+}
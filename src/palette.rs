@@ -0,0 +1,81 @@
+use std::cmp::Reverse;
+
+use crate::{
+    action::{Action, ACTIONS},
+    util::fuzzy_score,
+};
+
+/// State of the command palette, which runs any action by its name or description.
+#[derive(Debug)]
+pub struct Palette {
+    pub query: String,
+    /// Selected entry, as an index into [`Palette::entries`].
+    pub selected: usize,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Moves the selection by `delta` entries, staying within the entries.
+    pub fn move_selection(&mut self, delta: isize) {
+        let count = self.entries().len();
+        let selected = self.selected as isize + delta;
+        self.selected = selected.clamp(0, count.saturating_sub(1) as isize) as usize;
+    }
+
+    /// Returns the actions matching the query, best matches first.
+    pub fn entries(&self) -> Vec<Action> {
+        let mut entries = ACTIONS
+            .iter()
+            .filter(|(action, _, _)| *action != Action::CommandPalette)
+            .filter_map(|(action, name, description)| {
+                let score = fuzzy_score(&self.query, description)
+                    .max(fuzzy_score(&self.query, &name.replace('_', " ")))?;
+                Some((*action, score))
+            })
+            .collect::<Vec<_>>();
+        // The sort is stable, so equally good matches stay in the order of the actions.
+        entries.sort_by_key(|(_, score)| Reverse(*score));
+        entries.into_iter().map(|(action, _)| action).collect()
+    }
+
+    /// The selected action, if any matches the query.
+    pub fn selection(&self) -> Option<Action> {
+        self.entries().get(self.selected).copied()
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_palette_entries() {
+    let mut palette = Palette::new();
+    assert_eq!(palette.entries().len(), ACTIONS.len() - 1);
+    for c in "rand".chars() {
+        palette.push(c);
+    }
+    assert_eq!(palette.selection(), Some(Action::RandomPage));
+    palette.move_selection(-1);
+    assert_eq!(palette.selected, 0);
+    palette.push('#');
+    assert_eq!(palette.selection(), None);
+}
@@ -15,10 +15,24 @@ impl fmt::Display for Link {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// Url of the thumbnail, usually protocol relative.
+    pub src: String,
+    pub alt: String,
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "![{}]({})", self.alt, self.src)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ParagraphElement {
     Text(String, bool),
     Link(Link),
+    Image(Image),
 }
 
 impl fmt::Display for ParagraphElement {
@@ -26,18 +40,40 @@ impl fmt::Display for ParagraphElement {
         match self {
             ParagraphElement::Text(text, _) => write!(f, "{}", text),
             ParagraphElement::Link(link) => write!(f, "{}", link),
+            ParagraphElement::Image(image) => write!(f, "{}", image),
         }
     }
 }
 
+/// The kind of block a [`Paragraph`] was parsed from, used to render it distinctly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParagraphKind {
+    /// Regular article text.
+    Body,
+    /// Title of a section, from an `<h2>` to `<h4>`.
+    Heading,
+    /// Disambiguation and "Main article" notes, e.g. "For other uses, see ...".
+    Hatnote,
+    /// Quoted text from a `<blockquote>`.
+    Quote,
+    /// Caption of a `<figure>`.
+    Caption,
+    /// The image of a `<figure>`, holding a single [`ParagraphElement::Image`].
+    Figure,
+}
+
 #[derive(Debug, Clone)]
 pub struct Paragraph {
+    pub kind: ParagraphKind,
     pub elems: Vec<ParagraphElement>,
 }
 
 impl Paragraph {
-    fn new() -> Self {
-        Self { elems: vec![] }
+    fn new(kind: ParagraphKind) -> Self {
+        Self {
+            kind,
+            elems: vec![],
+        }
     }
 
     fn push(&mut self, elem: ParagraphElement) {
@@ -51,6 +87,7 @@ impl fmt::Display for Paragraph {
             match elem {
                 ParagraphElement::Text(text, _) => write!(f, "{}", text)?,
                 ParagraphElement::Link(link) => write!(f, "{}", link)?,
+                ParagraphElement::Image(image) => write!(f, "{}", image)?,
             }
         }
         Ok(())
@@ -66,77 +103,52 @@ pub struct Page {
 impl HtmlParser {
     pub fn parse_page(html: &str) -> Page {
         let document = Html::parse_document(html);
-        let selector = Selector::parse("p").unwrap();
+        let selector = Selector::parse(
+            "p, h2, h3, h4, blockquote, div.hatnote, div.dablink, div.rellink, figure, figcaption",
+        )
+        .unwrap();
 
         let mut paragraphs = Vec::new();
 
         for element in document.select(&selector) {
-            let mut paragraph = Paragraph::new();
-
-            for node in element.children() {
-                match node.value() {
-                    scraper::node::Node::Element(element_ref) => {
-                        let mut push_text_node = |bold| {
-                            // TODO: this code is bad
-                            let mut text = node
-                                .children()
-                                .filter_map(ElementRef::wrap)
-                                .flat_map(|el| el.text())
-                                .collect::<String>();
-
-                            if text.is_empty() {
-                                if let Some(first_child) = node.first_child() {
-                                    text = match first_child.value().as_text() {
-                                        None => "".to_string(),
-                                        Some(text) => text.to_string(),
-                                    };
-                                }
-                            }
-
-                            if !text.is_empty() {
-                                paragraph.push(ParagraphElement::Text(text, bold));
-                            }
-                        };
-
-                        match element_ref.name().to_string().as_str() {
-                            "span" => push_text_node(false),
-                            "b" => push_text_node(true),
-                            "i" => push_text_node(true),
-                            "a" => {
-                                // TODO: filter on wikimedia links
-                                if let Some(node) = node.first_child() {
-                                    if let Some(rel) = element_ref.attr("rel") {
-                                        if rel == "mw:WikiLink" {
-                                            paragraph.push(ParagraphElement::Link(Link {
-                                                link: element_ref
-                                                    .attr("href")
-                                                    .map(|v| v.to_string())
-                                                    .unwrap_or("??".to_string()),
-                                                text: node
-                                                    .value()
-                                                    .as_text()
-                                                    .map(|v| v.to_string())
-                                                    .unwrap_or("??".to_string()),
-                                            }));
-                                        }
-                                    }
-                                } else {
-                                    // TODO: this doesn't render external links somehow... Look at the Making History II page.
-                                    push_text_node(true);
-                                }
-                            }
-                            _ => {}
-                        }
+            let kind = match element.value().name() {
+                "p" => {
+                    let container = element.ancestors().filter_map(ElementRef::wrap).find(|a| {
+                        matches!(a.value().name(), "blockquote" | "figcaption")
+                            || Self::is_hatnote(*a)
+                    });
+                    match container.map(|a| a.value().name()) {
+                        Some("blockquote") => ParagraphKind::Quote,
+                        // Captions and hatnotes are parsed as a whole from their container.
+                        Some(_) => continue,
+                        None => ParagraphKind::Body,
                     }
-                    scraper::node::Node::Text(text) => {
-                        let text = text.text.to_string();
-                        if !text.trim().is_empty() {
-                            paragraph.push(ParagraphElement::Text(text, false));
-                        }
+                }
+                "blockquote" => {
+                    // Quotes made of paragraphs are handled by their `<p>` children.
+                    if element
+                        .select(&Selector::parse("p").unwrap())
+                        .next()
+                        .is_some()
+                    {
+                        continue;
                     }
-                    _ => (),
-                };
-            }
+                    ParagraphKind::Quote
+                }
+                "h2" | "h3" | "h4" => ParagraphKind::Heading,
+                "figcaption" => ParagraphKind::Caption,
+                "figure" => {
+                    if let Some(image) = Self::parse_figure_image(element) {
+                        let mut paragraph = Paragraph::new(ParagraphKind::Figure);
+                        paragraph.push(ParagraphElement::Image(image));
+                        paragraphs.push(paragraph);
+                    }
+                    continue;
+                }
+                _ => ParagraphKind::Hatnote,
+            };
+
+            let paragraph = Self::parse_paragraph(element, kind);
             if !paragraph.elems.is_empty() {
                 paragraphs.push(paragraph);
             }
@@ -157,4 +169,130 @@ impl HtmlParser {
 
         Page { title, paragraphs }
     }
+
+    fn is_hatnote(element: ElementRef) -> bool {
+        element.value().name() == "div"
+            && element
+                .value()
+                .classes()
+                .any(|c| matches!(c, "hatnote" | "dablink" | "rellink"))
+    }
+
+    fn parse_figure_image(figure: ElementRef) -> Option<Image> {
+        let img = figure.select(&Selector::parse("img").unwrap()).next()?;
+        let src = img.value().attr("src")?.to_string();
+        let alt = match img.value().attr("alt") {
+            Some(alt) if !alt.trim().is_empty() => alt.to_string(),
+            // Fall back to the file name, which is usually descriptive.
+            _ => img
+                .value()
+                .attr("resource")
+                .and_then(|r| r.rsplit_once(':'))
+                .map(|(_, name)| name.replace('_', " "))
+                .unwrap_or_default(),
+        };
+        Some(Image { src, alt })
+    }
+
+    fn parse_paragraph(element: ElementRef, kind: ParagraphKind) -> Paragraph {
+        let mut paragraph = Paragraph::new(kind);
+
+        for node in element.children() {
+            match node.value() {
+                scraper::node::Node::Element(element_ref) => {
+                    let mut push_text_node = |bold| {
+                        // TODO: this code is bad
+                        let mut text = node
+                            .children()
+                            .filter_map(ElementRef::wrap)
+                            .flat_map(|el| el.text())
+                            .collect::<String>();
+
+                        if text.is_empty() {
+                            if let Some(first_child) = node.first_child() {
+                                text = match first_child.value().as_text() {
+                                    None => "".to_string(),
+                                    Some(text) => text.to_string(),
+                                };
+                            }
+                        }
+
+                        if !text.is_empty() {
+                            paragraph.push(ParagraphElement::Text(text, bold));
+                        }
+                    };
+
+                    match element_ref.name().to_string().as_str() {
+                        "span" => push_text_node(false),
+                        "b" => push_text_node(true),
+                        "i" => push_text_node(true),
+                        "a" => {
+                            // TODO: filter on wikimedia links
+                            if let Some(node) = node.first_child() {
+                                if let Some(rel) = element_ref.attr("rel") {
+                                    if rel == "mw:WikiLink" {
+                                        paragraph.push(ParagraphElement::Link(Link {
+                                            link: element_ref
+                                                .attr("href")
+                                                .map(|v| v.to_string())
+                                                .unwrap_or("??".to_string()),
+                                            text: node
+                                                .value()
+                                                .as_text()
+                                                .map(|v| v.to_string())
+                                                .unwrap_or("??".to_string()),
+                                        }));
+                                    }
+                                }
+                            } else {
+                                // TODO: this doesn't render external links somehow... Look at the Making History II page.
+                                push_text_node(true);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                scraper::node::Node::Text(text) => {
+                    let text = text.text.to_string();
+                    if !text.trim().is_empty() {
+                        paragraph.push(ParagraphElement::Text(text, false));
+                    }
+                }
+                _ => (),
+            };
+        }
+        paragraph
+    }
+}
+
+#[test]
+fn test_parse_block_kinds() {
+    let html = r#"<html><head><title>T</title></head><body>
+        <div role="note" class="hatnote">For other uses, see <a rel="mw:WikiLink" href="./T_(disambiguation)">T (disambiguation)</a>.</div>
+        <h2 id="History">History</h2>
+        <p>Body text.</p>
+        <blockquote><p>A quote.</p></blockquote>
+        <figure><a href="./File:T.png"><img src="//t.png"/></a><figcaption>A <a rel="mw:WikiLink" href="./Caption">caption</a>.</figcaption></figure>
+        </body></html>"#;
+    let page = HtmlParser::parse_page(html);
+    let kinds: Vec<ParagraphKind> = page.paragraphs.iter().map(|p| p.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ParagraphKind::Hatnote,
+            ParagraphKind::Heading,
+            ParagraphKind::Body,
+            ParagraphKind::Quote,
+            ParagraphKind::Figure,
+            ParagraphKind::Caption
+        ]
+    );
+    assert!(matches!(
+        page.paragraphs[0].elems[1],
+        ParagraphElement::Link(_)
+    ));
+    assert!(matches!(
+        page.paragraphs[5].elems[1],
+        ParagraphElement::Link(_)
+    ));
 }
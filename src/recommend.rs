@@ -0,0 +1,189 @@
+use std::{collections::HashSet, error::Error};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    links::link_title,
+    parser::{HtmlParser, ParagraphElement},
+    session::Visit,
+    wikipedia::Wikipedia,
+};
+
+/// Most recent pages of a session the next page can be based on.
+const SEED_PAGES: usize = 20;
+/// Namespaces of links that do not lead to articles.
+const NAMESPACES: [&str; 8] = [
+    "File",
+    "Category",
+    "Help",
+    "Portal",
+    "Special",
+    "Talk",
+    "Template",
+    "Wikipedia",
+];
+
+/// Where a next page comes from, from coarse to fine grained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Any page, from the `page/random` endpoint.
+    Random,
+    /// A page in a category one up from a category of the seed page.
+    ParentCategory,
+    /// A page in the same category as the seed page.
+    Category,
+    /// A page similar to the seed page.
+    Related,
+    /// A page the seed page links to.
+    Links,
+}
+
+impl Source {
+    pub const ALL: [Source; 5] = [
+        Source::Random,
+        Source::ParentCategory,
+        Source::Category,
+        Source::Related,
+        Source::Links,
+    ];
+
+    /// How likely the source is picked for an exploration balance from 0 to 1. Without
+    /// exploration fine grained sources are preferred, close to the pages that were read,
+    /// with full exploration coarse grained ones.
+    pub fn weight(self, exploration: f64) -> f64 {
+        let grain = self as usize as f64 / (Self::ALL.len() - 1) as f64;
+        grain * (1.0 - exploration) + (1.0 - grain) * exploration
+    }
+}
+
+/// Where to get the next page from, and the page of the session it is based on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub source: Source,
+    pub seed: Option<String>,
+}
+
+impl Plan {
+    pub fn random() -> Self {
+        Self {
+            source: Source::Random,
+            seed: None,
+        }
+    }
+}
+
+/// Picks where the next page comes from. It is based on one of the recent pages of the
+/// session, more likely the more interesting it was, and its source is weighted by the
+/// exploration balance. Without interesting pages it is random.
+pub fn plan(visits: &[Visit], exploration: f64, rng: &mut impl Rng) -> Plan {
+    let seeds = visits
+        .iter()
+        .rev()
+        .take(SEED_PAGES)
+        .filter(|v| v.metrics.interest() > 0.0)
+        .collect::<Vec<_>>();
+    let Ok(seed) = seeds.choose_weighted(rng, |v| v.metrics.interest()) else {
+        return Plan::random();
+    };
+    match Source::ALL.choose_weighted(rng, |s| s.weight(exploration)) {
+        Ok(Source::Random) | Err(_) => Plan::random(),
+        Ok(&source) => Plan {
+            source,
+            seed: Some(seed.title.clone()),
+        },
+    }
+}
+
+/// Picks one of the candidates that were not visited yet.
+fn pick(candidates: Vec<String>, visited: &HashSet<String>) -> Option<String> {
+    let candidates = candidates
+        .into_iter()
+        .filter(|c| !visited.contains(c))
+        .collect::<Vec<_>>();
+    candidates.choose(&mut rand::thread_rng()).cloned()
+}
+
+/// Titles of the articles a page links to.
+fn linked_titles(html: &str) -> Vec<String> {
+    HtmlParser::parse_page(html)
+        .paragraphs
+        .iter()
+        .flat_map(|p| &p.elems)
+        .filter_map(|e| match e {
+            ParagraphElement::Link(link) => Some(link_title(&link.link)),
+            _ => None,
+        })
+        .filter(|title| match title.split_once(':') {
+            Some((namespace, _)) => !NAMESPACES.contains(&namespace),
+            None => true,
+        })
+        .collect()
+}
+
+/// Fetches the html of the page a plan leads to, one that is not in `visited`. Falls back
+/// to a random page when the source has no pages left.
+pub async fn fetch(
+    wikipedia: &Wikipedia,
+    plan: &Plan,
+    visited: &HashSet<String>,
+) -> Result<String, Box<dyn Error>> {
+    let none = HashSet::new();
+    let title = match (plan.source, &plan.seed) {
+        (Source::Random, _) | (_, None) => None,
+        (Source::Related, Some(seed)) => pick(wikipedia.related(seed).await?, visited),
+        (Source::Links, Some(seed)) => {
+            let html = wikipedia.get_page(&seed.replace(' ', "_")).await?;
+            pick(linked_titles(&html), visited)
+        }
+        (Source::Category, Some(seed)) => {
+            let category = pick(wikipedia.categories(seed).await?, &none);
+            match category {
+                Some(category) => pick(wikipedia.category_members(&category).await?, visited),
+                None => None,
+            }
+        }
+        (Source::ParentCategory, Some(seed)) => {
+            let category = pick(wikipedia.categories(seed).await?, &none);
+            let parent = match category {
+                Some(category) => pick(wikipedia.categories(&category).await?, &none),
+                None => None,
+            };
+            match parent {
+                Some(parent) => pick(wikipedia.category_members(&parent).await?, visited),
+                None => None,
+            }
+        }
+    };
+    match title {
+        Some(title) => wikipedia.get_page(&title.replace(' ', "_")).await,
+        None => wikipedia.random_page().await,
+    }
+}
+
+#[test]
+fn test_plan() {
+    use crate::session::Session;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut session = Session::new();
+    session.visit("Skimmed");
+    assert_eq!(plan(&session.visits, 0.0, &mut rng), Plan::random());
+
+    session.visit("Read");
+    session.visits[1].metrics.dwell = 120.0;
+    for exploration in [0.0, 1.0] {
+        for _ in 0..50 {
+            let plan = plan(&session.visits, exploration, &mut rng);
+            assert!(plan.source == Source::Random || plan.seed.as_deref() == Some("Read"));
+            assert_ne!(
+                plan.source,
+                [Source::Random, Source::Links][exploration as usize]
+            );
+        }
+    }
+
+    let html = "<html><body><p><a rel=\"mw:WikiLink\" href=\"./Lyon\">Lyon</a> and \
+                <a rel=\"mw:WikiLink\" href=\"./File:Map.png\">map</a></p></body></html>";
+    assert_eq!(linked_titles(html), vec!["Lyon"]);
+}
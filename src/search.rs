@@ -0,0 +1,124 @@
+use regex::{Regex, RegexBuilder};
+
+/// A match of the search in the page text, as a byte range in line `line`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// State of a search in the page.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+    /// Whether the query is a regular expression instead of plain text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Whether the query is still being typed.
+    pub editing: bool,
+    pub matches: Vec<Match>,
+    /// Index of the match that was jumped to.
+    pub current: Option<usize>,
+    /// Error of an invalid regular expression.
+    pub error: Option<String>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self {
+            editing: true,
+            ..Default::default()
+        }
+    }
+
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+    }
+
+    /// Finds every match in `lines`, the text of each rendered line.
+    pub fn find(&mut self, lines: &[String]) {
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        match self.pattern() {
+            Ok(pattern) => {
+                for (line, text) in lines.iter().enumerate() {
+                    self.matches
+                        .extend(
+                            pattern
+                                .find_iter(text)
+                                .filter(|m| !m.is_empty())
+                                .map(|m| Match {
+                                    line,
+                                    start: m.start(),
+                                    end: m.end(),
+                                }),
+                        );
+                }
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Selects the first match at or after `line`, or before it if `backwards`, wrapping
+    /// around the page.
+    pub fn select_from(&mut self, line: usize, backwards: bool) -> Option<Match> {
+        let index = if backwards {
+            self.matches
+                .iter()
+                .rposition(|m| m.line <= line)
+                .or(self.matches.len().checked_sub(1))
+        } else {
+            self.matches
+                .iter()
+                .position(|m| m.line >= line)
+                .or((!self.matches.is_empty()).then_some(0))
+        };
+        self.current = index;
+        index.map(|i| self.matches[i])
+    }
+
+    /// Selects the next match, or the previous one if `backwards`, wrapping around the page.
+    pub fn select_next(&mut self, backwards: bool) -> Option<Match> {
+        let count = self.matches.len();
+        if count == 0 {
+            return None;
+        }
+        let index = match (self.current, backwards) {
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count,
+            (None, false) => 0,
+            (None, true) => count - 1,
+        };
+        self.current = Some(index);
+        Some(self.matches[index])
+    }
+}
+
+#[test]
+fn test_search() {
+    let lines = vec!["The Cat sat".to_string(), "on the mat".to_string()];
+    let mut search = Search::new();
+    search.query = "the".to_string();
+    search.find(&lines);
+    assert_eq!(search.matches.len(), 2);
+    assert_eq!(search.select_from(1, false).map(|m| m.line), Some(1));
+    assert_eq!(search.select_next(false).map(|m| m.line), Some(0));
+
+    search.regex = true;
+    search.query = "[cm]at".to_string();
+    search.find(&lines);
+    assert_eq!(search.matches.len(), 2);
+}
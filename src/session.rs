@@ -0,0 +1,301 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{layout::Anchor, metrics::PageMetrics};
+
+/// A page opened in a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub title: String,
+    /// Unix time the page was opened at.
+    pub opened: i64,
+    /// Where the page was scrolled to when it was left.
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Interest shown in the page.
+    #[serde(default)]
+    pub metrics: PageMetrics,
+}
+
+/// Pages read in one sitting or more, saved as `<id>.json` in the session directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Name of the file, without extension, picked when the session is first saved.
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    /// Unix time the session was started at.
+    pub started: i64,
+    /// Every page opened, in order.
+    pub visits: Vec<Visit>,
+    /// Titles of the pages queued to read later, the next one first.
+    #[serde(default)]
+    pub read_later: Vec<String>,
+    /// The page queued to read later that is being fetched, see
+    /// [`next_read_later`](Self::next_read_later).
+    #[serde(skip)]
+    pub fetching: Option<String>,
+}
+
+impl Session {
+    /// Starts a session named after the time it is started at.
+    pub fn new() -> Self {
+        let now = Local::now();
+        Self {
+            id: String::new(),
+            name: now.format("%Y-%m-%d %H:%M").to_string(),
+            started: now.timestamp(),
+            visits: vec![],
+            read_later: vec![],
+            fetching: None,
+        }
+    }
+
+    /// Loads every session in `dir`, the most recently used first, along with the errors of
+    /// the files that could not be loaded. A missing directory has no sessions.
+    pub fn load_all(dir: &Path) -> Result<(Vec<Self>, Vec<String>), String> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], vec![])),
+            Err(e) => return Err(format!("Cannot read sessions in {}: {e}", dir.display())),
+        };
+        let mut sessions = vec![];
+        let mut errors = vec![];
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                match Self::load(&path) {
+                    Ok(session) => sessions.push(session),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_used()));
+        Ok((sessions, errors))
+    }
+
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read session {}: {e}", path.display()))?;
+        let mut session: Self = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid session {}: {e}", path.display()))?;
+        session.id = path
+            .file_stem()
+            .map_or(String::new(), |s| s.to_string_lossy().to_string());
+        Ok(session)
+    }
+
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.id))
+    }
+
+    pub fn save(&mut self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Cannot save sessions in {}: {e}", dir.display()))?;
+        if self.id.is_empty() {
+            self.id = self.new_id(dir)?;
+        }
+        let path = self.path(dir);
+        let text = serde_json::to_string_pretty(self).expect("Sessions are always valid JSON");
+        fs::write(&path, text).map_err(|e| format!("Cannot save session {}: {e}", path.display()))
+    }
+
+    /// Picks the name of the file from the time the session was started at, numbered if
+    /// another session was started in the same second. The file is created right away, so
+    /// that no other session picks it.
+    fn new_id(&self, dir: &Path) -> Result<String, String> {
+        let stamp = self.started_at().format("%Y%m%d-%H%M%S").to_string();
+        let mut id = stamp.clone();
+        for n in 2.. {
+            let path = dir.join(format!("{id}.json"));
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    id = format!("{stamp}-{n}");
+                }
+                Err(e) => return Err(format!("Cannot save session {}: {e}", path.display())),
+            }
+        }
+        Ok(id)
+    }
+
+    pub fn delete(&self, dir: &Path) -> Result<(), String> {
+        let path = self.path(dir);
+        fs::remove_file(&path).map_err(|e| format!("Cannot delete session {}: {e}", path.display()))
+    }
+
+    /// Records that a page was opened, unless it is the page that was already open, like
+    /// when a session is resumed.
+    pub fn visit(&mut self, title: &str) {
+        if self.last_visit().is_some_and(|v| v.title == title) {
+            return;
+        }
+        self.visits.push(Visit {
+            title: title.to_string(),
+            opened: Local::now().timestamp(),
+            anchor: Anchor::default(),
+            metrics: PageMetrics::default(),
+        });
+    }
+
+    /// Records that a page was opened again by going back to it, which continues its last
+    /// visit instead of starting another one.
+    pub fn revisit(&mut self, title: &str) {
+        match self.visits.iter().rposition(|v| v.title == title) {
+            Some(i) => {
+                let visit = self.visits.remove(i);
+                self.visits.push(visit);
+            }
+            None => self.visit(title),
+        }
+    }
+
+    /// Records where the current page is scrolled to.
+    pub fn scroll_to(&mut self, anchor: Anchor) {
+        if let Some(visit) = self.visits.last_mut() {
+            visit.anchor = anchor;
+        }
+    }
+
+    /// Queues a page to read later, unless it already is.
+    pub fn read_later(&mut self, title: &str) {
+        if !self.read_later.iter().any(|t| t == title) {
+            self.read_later.push(title.to_string());
+        }
+    }
+
+    /// The next page queued to read later. It stays queued while it is fetched, until
+    /// [`fetched_read_later`](Self::fetched_read_later) tells it has loaded, so that a failed
+    /// request does not lose it.
+    pub fn next_read_later(&mut self) -> Option<String> {
+        self.fetching = self.read_later.first().cloned();
+        self.fetching.clone()
+    }
+
+    /// Records that fetching the next page ended, which takes the page queued to read later
+    /// off the queue if it has loaded.
+    pub fn fetched_read_later(&mut self, loaded: bool) {
+        if let Some(title) = self.fetching.take() {
+            if loaded {
+                self.read_later.retain(|t| *t != title);
+            }
+        }
+    }
+
+    pub fn last_visit(&self) -> Option<&Visit> {
+        self.visits.last()
+    }
+
+    /// Number of different pages opened.
+    pub fn page_count(&self) -> usize {
+        let mut titles = self.visits.iter().map(|v| &v.title).collect::<Vec<_>>();
+        titles.sort();
+        titles.dedup();
+        titles.len()
+    }
+
+    /// Unix time of the last page opened, or of the start.
+    pub fn last_used(&self) -> i64 {
+        self.last_visit().map_or(self.started, |v| v.opened)
+    }
+
+    pub fn started_at(&self) -> DateTime<Local> {
+        Local
+            .timestamp_opt(self.started, 0)
+            .single()
+            .unwrap_or_else(Local::now)
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The screen listing the sessions on startup.
+#[derive(Debug)]
+pub struct SessionPicker {
+    pub sessions: Vec<Session>,
+    /// Selected session, or the new session entry after the last one.
+    pub selected: usize,
+    /// New name of the selected session while it is typed.
+    pub renaming: Option<String>,
+    /// Whether deleting the selected session waits for confirmation.
+    pub deleting: bool,
+}
+
+impl SessionPicker {
+    pub fn new(sessions: Vec<Session>) -> Self {
+        Self {
+            sessions,
+            selected: 0,
+            renaming: None,
+            deleting: false,
+        }
+    }
+
+    /// Moves the selection by `delta` entries, the new session entry included.
+    pub fn move_selection(&mut self, delta: isize) {
+        let selected = self.selected as isize + delta;
+        self.selected = selected.clamp(0, self.sessions.len() as isize) as usize;
+    }
+
+    pub fn selection(&self) -> Option<&Session> {
+        self.sessions.get(self.selected)
+    }
+}
+
+#[test]
+fn test_session_store() {
+    let dir = std::env::temp_dir().join(format!("clikipedia-sessions-{}", std::process::id()));
+    assert!(Session::load_all(&dir).unwrap().0.is_empty());
+
+    let mut session = Session::new();
+    session.visit("Rust");
+    session.visit("Iron");
+    session.visit("Iron");
+    session.scroll_to(Anchor {
+        paragraph: 3,
+        line: 0,
+        offset: 12,
+    });
+    session.visit("Rust");
+    // Going back to a page continues its last visit.
+    session.revisit("Iron");
+    session.save(&dir).unwrap();
+    fs::write(dir.join("broken.json"), "{").unwrap();
+
+    // Sessions started in the same second are saved to different files.
+    let mut twin = Session::new();
+    twin.started = session.started;
+    twin.visit("Lyon");
+    twin.save(&dir).unwrap();
+    assert_ne!(twin.id, session.id);
+
+    // A broken file does not hide the other sessions.
+    let (sessions, errors) = Session::load_all(&dir).unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(errors.len(), 1);
+    let loaded = sessions.iter().find(|s| s.id == session.id).unwrap();
+    assert_eq!(loaded.visits.len(), 3);
+    assert_eq!(loaded.page_count(), 2);
+    assert_eq!(loaded.last_visit().unwrap().title, "Iron");
+    assert_eq!(loaded.last_visit().unwrap().anchor.paragraph, 3);
+
+    for session in &sessions {
+        session.delete(&dir).unwrap();
+    }
+    fs::remove_file(dir.join("broken.json")).unwrap();
+    assert!(Session::load_all(&dir).unwrap().0.is_empty());
+    fs::remove_dir(&dir).unwrap();
+}
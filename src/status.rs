@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// A part of the status bar at the bottom of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusSegment {
+    /// Heading of the section at the top of the screen.
+    Section,
+    /// Number of links on the page.
+    Links,
+    /// Whether the page or its images are being fetched.
+    Loading,
+    /// Wikipedia pages are read from.
+    Site,
+    /// Time spent on the current page.
+    Time,
+    /// How much of the page was read, in percent.
+    Progress,
+    /// Strategy that picks next pages, not shown by default.
+    Strategy,
+}
+
+impl StatusSegment {
+    pub const DEFAULT: [StatusSegment; 6] = [
+        StatusSegment::Section,
+        StatusSegment::Loading,
+        StatusSegment::Links,
+        StatusSegment::Site,
+        StatusSegment::Time,
+        StatusSegment::Progress,
+    ];
+}
@@ -0,0 +1,245 @@
+use std::{collections::HashSet, error::Error, future::Future, pin::Pin};
+
+use crate::{
+    recommend::{self, Plan, Source},
+    session::Session,
+    wikipedia::Wikipedia,
+};
+
+/// Html of the next page, fetched in the background.
+pub type NextPage = Pin<Box<dyn Future<Output = Result<String, Box<dyn Error>>> + Send>>;
+
+/// What a strategy can base the next page on.
+pub struct Context<'a> {
+    pub wikipedia: &'a Wikipedia,
+    /// The session pages are recorded in, if any.
+    pub session: Option<&'a mut Session>,
+    /// Title of the open page, empty before the first one.
+    pub page_title: &'a str,
+    /// How far next pages may stray from the pages read, see [`recommend::plan`].
+    pub exploration: f64,
+}
+
+impl Context<'_> {
+    /// Titles of the pages read in the session.
+    pub fn visited(&self) -> HashSet<String> {
+        self.session
+            .iter()
+            .flat_map(|s| &s.visits)
+            .map(|v| v.title.clone())
+            .collect()
+    }
+
+    /// The open page, or the last page of the session before one is open.
+    pub fn current_page(&self) -> Option<String> {
+        if !self.page_title.is_empty() {
+            return Some(self.page_title.to_string());
+        }
+        let session = self.session.as_ref()?;
+        Some(session.last_visit()?.title.clone())
+    }
+
+    /// Fetches the page a plan leads to, skipping the pages read.
+    pub fn fetch(&self, plan: Plan) -> NextPage {
+        let wikipedia = self.wikipedia.clone();
+        let visited = self.visited();
+        Box::pin(async move { recommend::fetch(&wikipedia, &plan, &visited).await })
+    }
+
+    /// Fetches a page from `source` based on the open page, or a random page without one.
+    fn fetch_from_current(&self, source: Source) -> NextPage {
+        let plan = match self.current_page() {
+            Some(seed) => Plan {
+                source,
+                seed: Some(seed),
+            },
+            None => Plan::random(),
+        };
+        self.fetch(plan)
+    }
+}
+
+/// Decides what page comes next when asked for one, called by
+/// [`App::new_page`](crate::app::App::new_page).
+pub trait NextPageStrategy {
+    /// Name in the config and `--strategy`.
+    fn name(&self) -> &'static str;
+
+    /// What the strategy does, shown when switching to it.
+    fn description(&self) -> &'static str;
+
+    /// Picks the next page, returning the request that fetches it.
+    fn next_page(&self, context: Context) -> NextPage;
+}
+
+/// Any page, ignoring what was read.
+pub struct Random;
+
+impl NextPageStrategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn description(&self) -> &'static str {
+        "Random pages"
+    }
+
+    fn next_page(&self, context: Context) -> NextPage {
+        context.fetch(Plan::random())
+    }
+}
+
+/// Pages similar to the open one.
+pub struct Related;
+
+impl NextPageStrategy for Related {
+    fn name(&self) -> &'static str {
+        "related"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages related to the open one"
+    }
+
+    fn next_page(&self, context: Context) -> NextPage {
+        context.fetch_from_current(Source::Related)
+    }
+}
+
+/// Pages in a category of the open one, walking from category to category.
+pub struct CategoryWalk;
+
+impl NextPageStrategy for CategoryWalk {
+    fn name(&self) -> &'static str {
+        "category"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages in a category of the open one"
+    }
+
+    fn next_page(&self, context: Context) -> NextPage {
+        context.fetch_from_current(Source::Category)
+    }
+}
+
+/// Pages the open one links to, a random walk over the links.
+pub struct LinkWalk;
+
+impl NextPageStrategy for LinkWalk {
+    fn name(&self) -> &'static str {
+        "links"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages the open one links to"
+    }
+
+    fn next_page(&self, context: Context) -> NextPage {
+        context.fetch_from_current(Source::Links)
+    }
+}
+
+/// Pages picked from the pages read in the session, weighted by interest.
+pub struct SessionModel;
+
+impl NextPageStrategy for SessionModel {
+    fn name(&self) -> &'static str {
+        "session"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages picked from the most interesting pages read"
+    }
+
+    fn next_page(&self, context: Context) -> NextPage {
+        let plan = match &context.session {
+            Some(session) => recommend::plan(
+                &session.visits,
+                context.exploration,
+                &mut rand::thread_rng(),
+            ),
+            None => Plan::random(),
+        };
+        context.fetch(plan)
+    }
+}
+
+/// The pages queued to read later, in order, then pages of the session model.
+pub struct ReadLaterFirst;
+
+impl NextPageStrategy for ReadLaterFirst {
+    fn name(&self) -> &'static str {
+        "read_later"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pages queued to read later first"
+    }
+
+    fn next_page(&self, mut context: Context) -> NextPage {
+        let queued = context.session.as_mut().and_then(|s| s.next_read_later());
+        match queued {
+            Some(title) => {
+                let wikipedia = context.wikipedia.clone();
+                Box::pin(async move { wikipedia.get_page(&title.replace(' ', "_")).await })
+            }
+            None => SessionModel.next_page(context),
+        }
+    }
+}
+
+/// Every built-in strategy, the default first.
+pub fn built_in() -> Vec<Box<dyn NextPageStrategy>> {
+    vec![
+        Box::new(SessionModel),
+        Box::new(ReadLaterFirst),
+        Box::new(Random),
+        Box::new(Related),
+        Box::new(CategoryWalk),
+        Box::new(LinkWalk),
+    ]
+}
+
+/// Names of the built-in strategies, the default first.
+pub fn names() -> Vec<&'static str> {
+    built_in().iter().map(|s| s.name()).collect()
+}
+
+pub fn strategy_named(name: &str) -> Option<Box<dyn NextPageStrategy>> {
+    built_in().into_iter().find(|s| s.name() == name)
+}
+
+#[test]
+fn test_strategies() {
+    let mut names = names();
+    assert_eq!(names[0], "session");
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), built_in().len());
+    assert!(strategy_named("links").is_some());
+    assert!(strategy_named("popular").is_none());
+
+    let wikipedia = Wikipedia::new("en.wikipedia.org", "a@b.c");
+    let mut session = Session::new();
+    session.visit("Rust");
+    session.read_later("Iron");
+    let context = Context {
+        wikipedia: &wikipedia,
+        session: Some(&mut session),
+        page_title: "",
+        exploration: 0.3,
+    };
+    assert_eq!(context.current_page().as_deref(), Some("Rust"));
+    assert!(context.visited().contains("Rust"));
+    // The request is only sent when the future is polled.
+    drop(ReadLaterFirst.next_page(context));
+    assert_eq!(session.fetching.as_deref(), Some("Iron"));
+    // A page that failed to load stays queued, one that loaded is taken off the queue.
+    session.fetched_read_later(false);
+    assert_eq!(session.read_later, vec!["Iron"]);
+    assert_eq!(session.next_read_later().as_deref(), Some("Iron"));
+    session.fetched_read_later(true);
+    assert!(session.read_later.is_empty());
+    assert_eq!(session.next_read_later(), None);
+}
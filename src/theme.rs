@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use serde::{Deserialize, Serialize};
+
+/// Styles of the interface, named by what they are used for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    /// Article text, and the background of the page and popups.
+    pub body: Style,
+    pub heading: Style,
+    /// Text of a `<blockquote>`.
+    pub quote: Style,
+    /// Hatnotes and figure captions.
+    pub note: Style,
+    pub link: Style,
+    /// The link that is selected with a hint or focused with tab, and selected items in
+    /// lists and menus.
+    pub selected_link: Style,
+    pub hovered_link: Style,
+    /// The title bar, status bar and scrollbar.
+    pub title_bar: Style,
+    /// Borders of popups and panels.
+    pub border: Style,
+    pub error: Style,
+    pub warning: Style,
+    pub search_match: Style,
+    pub current_match: Style,
+    pub hint: Style,
+    /// Less important text, like the sentences in the link list.
+    pub muted: Style,
+    /// The search prompt and keys typed so far.
+    pub prompt: Style,
+}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 3] = ["light", "dark", "terminal"];
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            "terminal" => Some(Self::terminal()),
+            _ => None,
+        }
+    }
+
+    /// Black text on white, with light blue chrome.
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            body: Style::default().fg(Color::Black).bg(Color::White),
+            heading: Style::default().bold(),
+            quote: Style::default().italic(),
+            note: Style::default().italic(),
+            link: Style::default().fg(Color::Blue).underlined(),
+            selected_link: Style::default().fg(Color::White).bg(Color::Blue),
+            hovered_link: Style::default()
+                .fg(Color::White)
+                .bg(Color::LightBlue)
+                .underlined(),
+            title_bar: Style::default().fg(Color::White).bg(Color::LightBlue),
+            border: Style::default().fg(Color::LightBlue),
+            error: Style::default().fg(Color::White).bg(Color::Red),
+            warning: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
+            current_match: Style::default().fg(Color::White).bg(Color::LightRed),
+            hint: Style::default().fg(Color::Black).bg(Color::Yellow).bold(),
+            muted: Style::default().fg(Color::DarkGray),
+            prompt: Style::default().fg(Color::Black).bg(Color::Gray),
+        }
+    }
+
+    /// Light gray text on black, with blue chrome.
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            body: Style::default().fg(Color::Gray).bg(Color::Black),
+            heading: Style::default().fg(Color::White).bold(),
+            quote: Style::default().fg(Color::White).italic(),
+            note: Style::default().fg(Color::DarkGray).italic(),
+            link: Style::default().fg(Color::LightCyan).underlined(),
+            selected_link: Style::default().fg(Color::Black).bg(Color::LightCyan),
+            hovered_link: Style::default()
+                .fg(Color::White)
+                .bg(Color::DarkGray)
+                .underlined(),
+            title_bar: Style::default().fg(Color::White).bg(Color::Blue),
+            border: Style::default().fg(Color::Blue),
+            error: Style::default().fg(Color::White).bg(Color::Red),
+            warning: Style::default().fg(Color::Black).bg(Color::Yellow),
+            search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
+            current_match: Style::default().fg(Color::Black).bg(Color::LightRed),
+            hint: Style::default().fg(Color::Black).bg(Color::Yellow).bold(),
+            muted: Style::default().fg(Color::DarkGray),
+            prompt: Style::default().fg(Color::White).bg(Color::DarkGray),
+        }
+    }
+
+    /// The colors of the terminal, with modifiers instead of colors where possible.
+    pub fn terminal() -> Self {
+        Self {
+            name: "terminal".to_string(),
+            body: Style::default(),
+            heading: Style::default().bold(),
+            quote: Style::default().italic(),
+            note: Style::default().italic().dim(),
+            link: Style::default().fg(Color::Blue).underlined(),
+            selected_link: Style::default().reversed(),
+            hovered_link: Style::default().bold().underlined(),
+            title_bar: Style::default().reversed(),
+            border: Style::default(),
+            error: Style::default().fg(Color::Red).bold().reversed(),
+            warning: Style::default().fg(Color::Yellow).reversed(),
+            search_match: Style::default().reversed(),
+            current_match: Style::default().reversed().bold(),
+            hint: Style::default().fg(Color::Yellow).bold().reversed(),
+            muted: Style::default().dim(),
+            prompt: Style::default().reversed(),
+        }
+    }
+
+    /// Names of the styles, as used in the config file.
+    pub const STYLES: [&'static str; 16] = [
+        "body",
+        "heading",
+        "quote",
+        "note",
+        "link",
+        "selected_link",
+        "hovered_link",
+        "title_bar",
+        "border",
+        "error",
+        "warning",
+        "search_match",
+        "current_match",
+        "hint",
+        "muted",
+        "prompt",
+    ];
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "body" => &mut self.body,
+            "heading" => &mut self.heading,
+            "quote" => &mut self.quote,
+            "note" => &mut self.note,
+            "link" => &mut self.link,
+            "selected_link" => &mut self.selected_link,
+            "hovered_link" => &mut self.hovered_link,
+            "title_bar" => &mut self.title_bar,
+            "border" => &mut self.border,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            "search_match" => &mut self.search_match,
+            "current_match" => &mut self.current_match,
+            "hint" => &mut self.hint,
+            "muted" => &mut self.muted,
+            "prompt" => &mut self.prompt,
+            _ => return None,
+        })
+    }
+
+    /// Builds a user defined theme, its styles patched onto the ones of a built-in theme.
+    pub fn from_spec(name: &str, spec: &ThemeSpec) -> Result<Self, String> {
+        let base = spec.base.as_deref().unwrap_or("light");
+        let mut theme = Self::built_in(base).ok_or_else(|| {
+            format!(
+                "Theme '{name}' is based on '{base}', expected one of {}",
+                Self::BUILT_IN.join(", ")
+            )
+        })?;
+        theme.name = name.to_string();
+        for (style_name, style) in &spec.styles {
+            let target = theme.style_mut(style_name).ok_or_else(|| {
+                format!(
+                    "Theme '{name}' has unknown style '{style_name}', expected one of {}",
+                    Self::STYLES.join(", ")
+                )
+            })?;
+            *target = style.apply(*target);
+        }
+        Ok(theme)
+    }
+}
+
+/// A user defined theme in the config file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeSpec {
+    /// Built-in theme the styles are patched onto, `light` by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub styles: BTreeMap<String, StyleSpec>,
+}
+
+/// Changes to a style, e.g. `{ fg = "#268bd2", underlined = true }`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleSpec {
+    #[serde(with = "color", skip_serializing_if = "Option::is_none")]
+    pub fg: Option<Color>,
+    #[serde(with = "color", skip_serializing_if = "Option::is_none")]
+    pub bg: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dim: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reversed: Option<bool>,
+}
+
+impl StyleSpec {
+    fn apply(&self, mut style: Style) -> Style {
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        for (set, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.dim, Modifier::DIM),
+            (self.reversed, Modifier::REVERSED),
+        ] {
+            match set {
+                Some(true) => style = style.add_modifier(modifier),
+                Some(false) => style = style.remove_modifier(modifier),
+                None => {}
+            }
+        }
+        style
+    }
+}
+
+/// Colors as names like `light blue`, `#rrggbb` hex codes or indexes in the palette.
+mod color {
+    use ratatui::style::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        color: &Option<Color>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match color {
+            Some(color) => serializer.serialize_str(&color.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Color>, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map(Some)
+            .map_err(|_| D::Error::custom(format!("unknown color '{name}'")))
+    }
+}
+
+#[test]
+fn test_theme_from_spec() {
+    let spec: ThemeSpec =
+        toml::from_str("base = \"dark\"\nlink = { fg = \"#268bd2\", underlined = false }").unwrap();
+    let theme = Theme::from_spec("solarized", &spec).unwrap();
+    assert_eq!(
+        theme.link,
+        Style::default()
+            .fg(Color::Rgb(0x26, 0x8b, 0xd2))
+            .not_underlined()
+    );
+    assert_eq!(theme.body, Theme::dark().body);
+
+    let spec: ThemeSpec = toml::from_str("links = { fg = \"blue\" }").unwrap();
+    assert!(Theme::from_spec("typo", &spec).is_err());
+    assert!(toml::from_str::<ThemeSpec>("link = { fg = \"blurple\" }").is_err());
+}
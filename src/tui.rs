@@ -1,12 +1,20 @@
 use crate::app::{App, AppResult};
-use crate::event::EventHandler;
+use crate::graphics::{self, Placement, Protocol};
 use crate::ui;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::cursor::MoveTo;
+use crossterm::event::{
+    DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::Backend;
 use ratatui::Terminal;
-use std::io;
+use std::io::{self, Write};
 use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether keyboard enhancement flags were pushed, so that they are popped on exit.
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
 
 /// Representation of a terminal user interface.
 ///
@@ -16,22 +24,45 @@ use std::panic;
 pub struct Tui<B: Backend> {
     /// Interface to the Terminal.
     terminal: Terminal<B>,
-    /// Terminal event handler.
-    pub events: EventHandler,
+    /// Images drawn with a terminal graphics protocol during the last draw.
+    placements: Vec<Placement>,
 }
 
 impl<B: Backend> Tui<B> {
     /// Constructs a new instance of [`Tui`].
-    pub fn new(terminal: Terminal<B>, events: EventHandler) -> Self {
-        Self { terminal, events }
+    pub fn new(terminal: Terminal<B>) -> Self {
+        Self {
+            terminal,
+            placements: vec![],
+        }
     }
 
     /// Initializes the terminal interface.
     ///
-    /// It enables the raw mode and sets terminal properties.
-    pub fn init(&mut self) -> AppResult<()> {
+    /// It enables the raw mode and sets terminal properties. Returns whether the terminal
+    /// supports the kitty keyboard protocol, which reports keys like Shift+Space. It has to
+    /// be called before the [`EventHandler`](crate::event::EventHandler) starts reading
+    /// events, or the answer of the terminal is not seen.
+    pub fn init(&mut self) -> AppResult<bool> {
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+        crossterm::execute!(
+            io::stderr(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableFocusChange
+        )?;
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            crossterm::execute!(
+                io::stderr(),
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                )
+            )?;
+            KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+        }
 
         // Define a custom panic hook to reset the terminal properties.
         // This way, you won't have your terminal messed up if an unexpected error happens.
@@ -43,7 +74,7 @@ impl<B: Backend> Tui<B> {
 
         self.terminal.hide_cursor()?;
         self.terminal.clear()?;
-        Ok(())
+        Ok(enhanced)
     }
 
     /// [`Draw`] the terminal interface by [`rendering`] the widgets.
@@ -52,6 +83,33 @@ impl<B: Backend> Tui<B> {
     /// [`rendering`]: crate::ui:render
     pub fn draw(&mut self, app: &mut App) -> AppResult<()> {
         self.terminal.draw(|frame| ui::render(app, frame))?;
+        if app.graphics.is_pixel_based() && app.image_placements != self.placements {
+            self.draw_images(app)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the images on screen over the lines reserved for them, with escape sequences
+    /// that bypass the ratatui buffer.
+    fn draw_images(&mut self, app: &mut App) -> AppResult<()> {
+        let mut stderr = io::stderr();
+        match app.graphics {
+            Protocol::Kitty => stderr.write_all(graphics::kitty_clear().as_bytes())?,
+            // Sixels stay on screen until overwritten, so redraw everything to erase them.
+            _ => {
+                self.terminal.clear()?;
+                self.terminal.draw(|frame| ui::render(app, frame))?;
+            }
+        }
+
+        for placement in &app.image_placements {
+            if let Some(image) = app.image(&placement.src) {
+                crossterm::queue!(stderr, MoveTo(placement.x, placement.y))?;
+                stderr.write_all(graphics::encode(app.graphics, image, placement).as_bytes())?;
+            }
+        }
+        stderr.flush()?;
+        self.placements = app.image_placements.clone();
         Ok(())
     }
 
@@ -60,8 +118,16 @@ impl<B: Backend> Tui<B> {
     /// This function is also used for the panic hook to revert
     /// the terminal properties if unexpected errors occur.
     fn reset() -> AppResult<()> {
+        if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+            crossterm::execute!(io::stderr(), PopKeyboardEnhancementFlags)?;
+        }
         terminal::disable_raw_mode()?;
-        crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+        crossterm::execute!(
+            io::stderr(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableFocusChange
+        )?;
         Ok(())
     }
 
@@ -1,20 +1,61 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::app::App;
+use crate::{
+    action::{Action, ACTIONS},
+    app::{App, ImageSlot, Preview, NAVIGATION_MENU},
+    graphics::Placement,
+    keymap::sequence_name,
+    layout::Layout,
+    links::{LinkList, LinkOrder},
+    notifications::Severity,
+    palette::Palette,
+    parser::Link,
+    search::Search,
+    session::SessionPicker,
+    status::StatusSegment,
+    theme::Theme,
+};
+
+/// Maximum width of the link preview popup.
+const PREVIEW_WIDTH: u16 = 60;
+/// Width of the navigation menu, including its border.
+const MENU_WIDTH: u16 = 20;
+/// Smallest screen the page is shown on, below it only a message is.
+pub const MIN_WIDTH: u16 = 20;
+pub const MIN_HEIGHT: u16 = 4;
+/// Below this width panels and popups take the whole screen instead of leaving margins.
+const NARROW_WIDTH: u16 = 50;
+const STATUS_SEPARATOR: &str = " │ ";
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
     app.resize(frame.size().width, frame.size().height);
+    let theme = app.theme.clone();
+    if frame.size().width < MIN_WIDTH || frame.size().height < MIN_HEIGHT {
+        // Nothing of the page is on screen to click on or draw images over.
+        app.content_area = Rect::default();
+        app.image_placements.clear();
+        render_too_small(&theme, frame);
+        return;
+    }
+    if let Some(picker) = &app.session_picker {
+        app.content_area = Rect::default();
+        app.image_placements.clear();
+        render_session_picker(picker, &theme, frame);
+        render_toasts(app, &theme, frame);
+        return;
+    }
 
     // Title bar
     frame.render_widget(
-        Paragraph::new(app.page_title.clone())
-            .style(Style::default().fg(Color::White).bg(Color::LightBlue)),
+        Paragraph::new(app.page_title.clone()).style(theme.title_bar),
         Rect {
             x: 0,
             y: 0,
@@ -23,24 +64,23 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         },
     );
 
-    // Page content
-    let p = Paragraph::new(app.get_text())
-        .style(Style::default().fg(Color::Black).bg(Color::White))
-        .wrap(Wrap { trim: false })
-        .scroll((app.vertical_scroll as u16, 0));
-    let content_length = p.line_count(frame.size().width - 1);
-
-    frame.render_widget(
-        p,
-        Rect {
-            x: 0,
-            y: 1,
-            width: frame.size().width - 1, // TODO: this should be a value accesible from everywhere
-            height: frame.size().height - 1,
-        },
+    // Page content, in a column with margins on wide screens
+    let content_area = app.column_area();
+    frame.render_widget(Block::default().style(theme.body), app.page_area());
+    app.layout_page(content_area);
+    app.image_placements = image_placements(
+        &app.layout,
+        app.image_slots(),
+        app.vertical_scroll,
+        content_area,
     );
 
-    app.set_scroll_params(content_length);
+    // The layout is already wrapped, so only the rows on screen are rendered.
+    let first_row = app.vertical_scroll.min(app.layout.rows.len());
+    let last_row = (first_row + content_area.height as usize).min(app.layout.rows.len());
+    let p = Paragraph::new(app.layout.rows[first_row..last_row].to_vec()).style(theme.body);
+
+    frame.render_widget(p, content_area);
 
     // Scrollbar
     frame.render_stateful_widget(
@@ -48,25 +88,51 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             .orientation(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("|"))
             .end_symbol(Some("|"))
-            .style(Style::default().fg(Color::White).bg(Color::LightBlue)),
-        Rect {
-            x: frame.size().width - 1,
-            y: 1,
-            width: 1,
-            height: frame.size().height - 1,
-        },
+            .style(theme.title_bar),
+        app.scrollbar_area(),
         &mut app.vertical_scroll_state,
     );
 
+    if app.status_bar_shown() {
+        render_status_bar(app, &theme, frame);
+    }
+
+    // Search matches on the scrollbar and the search prompt
+    if let Some(search) = &app.search {
+        let rows = app.layout.rows.len().max(1);
+        let scrollbar = app.scrollbar_area();
+        for m in &search.matches {
+            let row = app.layout.row_of(m.line, m.start);
+            let y = scrollbar.y + (row * scrollbar.height as usize / rows) as u16;
+            frame
+                .buffer_mut()
+                .get_mut(scrollbar.x, y)
+                .set_symbol("━")
+                .set_style(theme.search_match);
+        }
+        render_search(search, &theme, frame);
+    }
+
+    // Link hints
+    for (label, x, y) in app.hint_labels() {
+        let area = Rect {
+            x,
+            y,
+            width: label.len() as u16,
+            height: 1,
+        }
+        .intersection(content_area);
+        frame.render_widget(Paragraph::new(label).style(theme.hint), area);
+    }
+
     // Link selector box
     if !app.selector.is_empty() {
         let text = format!("[{}]", app.selector);
         let width = text.len() as u16;
-        let mut style = Style::default().bg(Color::Gray);
-        if app.link_selector_exists() {
-            style = style.fg(Color::Blue).bg(Color::White);
+        let style = if app.link_selector_exists() {
+            theme.body.patch(theme.link)
         } else {
-            style = style.fg(Color::White).bg(Color::Red);
+            theme.error
         };
         frame.render_widget(
             Paragraph::new(text).style(style),
@@ -78,4 +144,660 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             },
         )
     }
+
+    // Keys of an incomplete sequence, like the count of `5j`
+    let pending = app.keymap.pending();
+    if app.selector.is_empty() && !pending.is_empty() {
+        frame.render_widget(
+            Paragraph::new(pending.clone()).style(theme.prompt),
+            Rect {
+                x: 0,
+                y: frame.size().height - 1,
+                width: pending.chars().count() as u16,
+                height: 1,
+            },
+        )
+    }
+
+    // Link preview
+    if let Some((link, preview)) = app.selected_preview() {
+        render_preview(link, preview, &theme, frame);
+    }
+
+    // Link list
+    if let Some(list) = &app.link_list {
+        render_link_list(app, list, &theme, frame);
+    }
+
+    // Notifications, above the status bar
+    render_toasts(app, &theme, frame);
+
+    // Command palette and help
+    if let Some(first) = app.notification_log {
+        render_notification_log(app, first, &theme, frame);
+    }
+    if let Some(palette) = &app.palette {
+        render_palette(app, palette, &theme, frame);
+    }
+    if let Some(first) = app.help {
+        render_help(app, first, &theme, frame);
+    }
+
+    // Navigation menu
+    if let Some(selected) = app.menu {
+        render_menu(selected, &theme, frame);
+    }
+}
+
+/// Renders a message asking for a bigger terminal, as much of it as fits.
+fn render_too_small(theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    let text = format!(
+        "Terminal too small ({}x{}), needs {MIN_WIDTH}x{MIN_HEIGHT}",
+        size.width, size.height
+    );
+    frame.render_widget(
+        Paragraph::new(text)
+            .style(theme.body)
+            .wrap(Wrap { trim: true }),
+        size,
+    );
+}
+
+/// Renders the sessions with their start date and page count, and the keys to manage them
+/// on the bottom line.
+fn render_session_picker(picker: &SessionPicker, theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    frame.render_widget(
+        Paragraph::new("Sessions").style(theme.title_bar),
+        Rect { height: 1, ..size },
+    );
+
+    let area = Rect {
+        y: 1,
+        height: size.height - 2,
+        ..size
+    };
+    let width = picker
+        .sessions
+        .iter()
+        .map(|s| s.name.width())
+        .max()
+        .unwrap_or(0)
+        .min(size.width as usize / 2);
+    let mut lines = picker
+        .sessions
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let name = match &picker.renaming {
+                Some(name) if i == picker.selected => format!("{name}▏"),
+                _ => truncate(&session.name, width),
+            };
+            let pages = session.page_count();
+            Line::from(vec![
+                Span::raw(format!(" {name:<width$}  ")),
+                Span::styled(
+                    format!(
+                        "{}  {pages} page{}",
+                        session.started_at().format("%Y-%m-%d %H:%M"),
+                        if pages == 1 { "" } else { "s" }
+                    ),
+                    theme.muted,
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    lines.push(Line::from(Span::styled(" New session", theme.link)));
+    lines[picker.selected].patch_style(theme.selected_link);
+
+    // Keep the selection on screen.
+    let offset = picker
+        .selected
+        .saturating_sub(area.height.saturating_sub(1) as usize);
+    frame.render_widget(
+        Paragraph::new(lines.split_off(offset)).style(theme.body),
+        area,
+    );
+
+    let (prompt, style) = if picker.deleting {
+        ("Delete this session? y/n", theme.warning)
+    } else if picker.renaming.is_some() {
+        ("Type a name, enter to save, esc to cancel", theme.prompt)
+    } else {
+        (
+            "enter: open  n: new  r: rename  d: delete  q: quit",
+            theme.prompt,
+        )
+    };
+    frame.render_widget(
+        Paragraph::new(truncate(prompt, size.width as usize)).style(style),
+        Rect {
+            y: size.height - 1,
+            height: 1,
+            ..size
+        },
+    );
+}
+
+/// Renders the status bar on the bottom line, the section heading cut off to leave room
+/// for the other segments.
+fn render_status_bar(app: &App, theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    let segments = app
+        .config
+        .status_bar
+        .iter()
+        .filter_map(|&segment| Some((segment, status_text(app, segment)?)))
+        .collect::<Vec<_>>();
+    let fixed_width = segments
+        .iter()
+        .filter(|(segment, _)| *segment != StatusSegment::Section)
+        .map(|(_, text)| text.width() + STATUS_SEPARATOR.width())
+        .sum::<usize>();
+    let section_width = (size.width as usize).saturating_sub(fixed_width + 1);
+
+    let text = segments
+        .into_iter()
+        .map(|(segment, text)| match segment {
+            StatusSegment::Section => truncate(&text, section_width),
+            _ => text,
+        })
+        .collect::<Vec<_>>()
+        .join(STATUS_SEPARATOR);
+    frame.render_widget(
+        Paragraph::new(format!(" {text}")).style(theme.title_bar),
+        Rect {
+            x: 0,
+            y: size.height - 1,
+            width: size.width,
+            height: 1,
+        },
+    );
+}
+
+/// Text of a status bar segment, if it has anything to show.
+fn status_text(app: &App, segment: StatusSegment) -> Option<String> {
+    match segment {
+        StatusSegment::Section => app.current_section(),
+        StatusSegment::Links => Some(match app.link_count() {
+            1 => "1 link".to_string(),
+            count => format!("{count} links"),
+        }),
+        StatusSegment::Loading => match app.images_loading() {
+            _ if app.loading => Some("loading page".to_string()),
+            (0, _) => None,
+            (loading, total) => Some(format!("images {}/{total}", total - loading)),
+        },
+        StatusSegment::Site => Some(app.config.site.clone()),
+        StatusSegment::Time => Some(format!("{}m", app.time_on_page().as_secs() / 60)),
+        StatusSegment::Progress => Some(format!("{}%", app.progress())),
+        StatusSegment::Strategy => Some(format!("next: {}", app.strategy_name())),
+    }
+}
+
+/// Cuts `text` off at `width` columns, ending it with an ellipsis if it is cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    for c in text.chars() {
+        if truncated.width() + c.width().unwrap_or(0) + 1 > width {
+            break;
+        }
+        truncated.push(c);
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// Renders the search prompt while typing, or the match count on the bottom line.
+fn render_search(search: &Search, theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    let status = match (&search.error, search.matches.len(), search.current) {
+        (Some(error), _, _) => error.lines().last().unwrap_or_default().to_string(),
+        (None, 0, _) => "no matches".to_string(),
+        (None, count, Some(current)) => format!("{}/{count}", current + 1),
+        (None, count, None) => format!("{count} matches"),
+    };
+    let mut modes = vec![];
+    if search.regex {
+        modes.push("regex");
+    }
+    if search.case_sensitive {
+        modes.push("case sensitive");
+    }
+    let modes = if modes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", modes.join(", "))
+    };
+
+    let text = if search.editing && size.width >= NARROW_WIDTH {
+        format!("/{}{modes}  {status}", search.query)
+    } else {
+        format!("/{}  {status}", search.query)
+    };
+    let style = if search.error.is_some() {
+        theme.error
+    } else {
+        theme.prompt
+    };
+    let width = (text.chars().count() as u16).min(size.width);
+    frame.render_widget(
+        Paragraph::new(text).style(style),
+        Rect {
+            x: size.width - width,
+            y: size.height.saturating_sub(1),
+            width,
+            height: 1,
+        },
+    );
+}
+
+/// Renders the panel listing the links on the page, filtered by its query.
+fn render_link_list(app: &App, list: &LinkList, theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    let narrow = size.width < NARROW_WIDTH;
+    let area = panel_area(size);
+    let entries = app.link_list_entries();
+    let order = match list.order {
+        LinkOrder::Document => "document order",
+        LinkOrder::Frequency => "most linked first",
+    };
+
+    // The first line holds the query, the rest are entries, scrolled to keep the
+    // selection in view.
+    let visible = area.height.saturating_sub(3) as usize;
+    let offset = list.selected.saturating_sub(visible.saturating_sub(1));
+    let mut lines = vec![Line::from(format!("> {}", list.query))];
+    for (i, entry) in entries.iter().enumerate().skip(offset).take(visible) {
+        let mut line = Line::from(vec![
+            Span::styled(entry.link.text.clone(), theme.link),
+            Span::raw(format!(" → {}", entry.title)),
+        ]);
+        // The sentence would be cut off right away on narrow screens.
+        if !narrow {
+            line.spans
+                .push(Span::styled(format!("  {}", entry.context), theme.muted));
+        }
+        if i == list.selected {
+            line.patch_style(theme.selected_link);
+        }
+        lines.push(line);
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).style(theme.body).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title(if narrow {
+                    format!("Links: {}", entries.len())
+                } else {
+                    format!("Links: {} ({order}, tab to change)", entries.len())
+                }),
+        ),
+        area,
+    );
+}
+
+/// Area of the panels, with margins around it unless the screen is narrow.
+fn panel_area(size: Rect) -> Rect {
+    if size.width < NARROW_WIDTH {
+        Rect {
+            y: 1,
+            height: size.height - 1,
+            ..size
+        }
+    } else {
+        Rect {
+            x: size.width / 10,
+            y: size.height / 10,
+            width: size.width - size.width / 5,
+            height: size.height - size.height / 5,
+        }
+    }
+}
+
+/// Whether the screen cell at `x`, `y` is on a panel, on a screen of `size`.
+pub fn on_panel(size: Rect, x: u16, y: u16) -> bool {
+    let area = panel_area(size);
+    x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height
+}
+
+/// The keys bound to an action in the active keymap.
+fn action_keys(app: &App, action: Action) -> String {
+    app.keymap
+        .bindings()
+        .iter()
+        .filter(|(_, a)| *a == action)
+        .map(|(keys, _)| sequence_name(keys))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Renders every action with the keys bound to it, starting at action `first`.
+fn render_help(app: &App, first: usize, theme: &Theme, frame: &mut Frame) {
+    let area = panel_area(frame.size());
+    let keys = ACTIONS
+        .iter()
+        .map(|(action, _, _)| action_keys(app, *action))
+        .collect::<Vec<_>>();
+    let width = keys.iter().map(|k| k.width()).max().unwrap_or(0);
+    let lines = ACTIONS
+        .iter()
+        .zip(&keys)
+        .skip(first)
+        .map(|((_, _, description), keys)| {
+            let style = if keys.is_empty() {
+                theme.muted
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{keys:<width$}  "), theme.heading),
+                Span::styled(*description, style),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).style(theme.body).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title("Keys (esc to close)"),
+        ),
+        area,
+    );
+}
+
+/// Renders the command palette, the actions matching its query with their keys.
+fn render_palette(app: &App, palette: &Palette, theme: &Theme, frame: &mut Frame) {
+    let area = panel_area(frame.size());
+    let entries = palette.entries();
+
+    // Like the link list, the first line holds the query.
+    let visible = area.height.saturating_sub(3) as usize;
+    let offset = palette.selected.saturating_sub(visible.saturating_sub(1));
+    let mut lines = vec![Line::from(format!("> {}", palette.query))];
+    for (i, action) in entries.iter().enumerate().skip(offset).take(visible) {
+        let mut line = Line::from(vec![
+            Span::raw(action.description()),
+            Span::styled(format!("  {}", action_keys(app, *action)), theme.muted),
+        ]);
+        if i == palette.selected {
+            line.patch_style(theme.selected_link);
+        }
+        lines.push(line);
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).style(theme.body).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title(format!("Actions: {}", entries.len())),
+        ),
+        area,
+    );
+}
+
+fn severity_style(severity: Severity, theme: &Theme) -> Style {
+    match severity {
+        Severity::Info => theme.prompt,
+        Severity::Warning => theme.warning,
+        Severity::Error => theme.error,
+    }
+}
+
+/// Renders the notifications on screen in the bottom right corner, the newest at the
+/// bottom.
+fn render_toasts(app: &App, theme: &Theme, frame: &mut Frame) {
+    let size = frame.size();
+    let bottom = size
+        .height
+        .saturating_sub(1 + app.status_bar_shown() as u16);
+    for (i, notification) in app.notifications.shown().iter().rev().enumerate() {
+        let Some(y) = bottom.checked_sub(i as u16 + 1).filter(|&y| y > 0) else {
+            break;
+        };
+        let text = format!(
+            " {} ",
+            truncate(&notification.message, size.width.saturating_sub(4) as usize)
+        );
+        let width = (text.width() as u16).min(size.width);
+        frame.render_widget(
+            Paragraph::new(text).style(severity_style(notification.severity, theme)),
+            Rect {
+                x: size.width.saturating_sub(width + 1),
+                y,
+                width,
+                height: 1,
+            },
+        );
+    }
+}
+
+/// Renders the past notifications, newest first, starting at notification `first`.
+fn render_notification_log(app: &App, first: usize, theme: &Theme, frame: &mut Frame) {
+    let area = panel_area(frame.size());
+    let history = app.notifications.history();
+    let lines = history
+        .iter()
+        .rev()
+        .skip(first)
+        .map(|notification| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{} ", notification.time.format("%H:%M:%S")),
+                    theme.muted,
+                ),
+                Span::styled(
+                    notification.message.clone(),
+                    match notification.severity {
+                        Severity::Info => Style::default(),
+                        severity => severity_style(severity, theme),
+                    },
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .style(theme.body)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border)
+                    .title(format!("Notifications: {}", history.len())),
+            ),
+        area,
+    );
+}
+
+/// Area of the navigation menu, right below the title bar.
+fn menu_area() -> Rect {
+    Rect {
+        x: 0,
+        y: 1,
+        width: MENU_WIDTH,
+        height: NAVIGATION_MENU.len() as u16 + 2,
+    }
+}
+
+/// Returns the navigation menu item on the screen cell at `x`, `y`.
+pub fn menu_item_at(x: u16, y: u16) -> Option<usize> {
+    let area = menu_area();
+    if x > area.x && x < area.x + area.width - 1 && y > area.y && y < area.y + area.height - 1 {
+        Some((y - area.y - 1) as usize)
+    } else {
+        None
+    }
+}
+
+fn render_menu(selected: usize, theme: &Theme, frame: &mut Frame) {
+    let lines = NAVIGATION_MENU
+        .iter()
+        .enumerate()
+        .map(|(i, (_, name))| {
+            if i == selected {
+                Line::from(Span::styled(*name, theme.selected_link))
+            } else {
+                Line::from(*name)
+            }
+        })
+        .collect::<Vec<_>>();
+    let area = menu_area().intersection(frame.size());
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).style(theme.body).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border),
+        ),
+        area,
+    );
+}
+
+/// Renders a popup above the link selector box with a summary of the page a link points to.
+fn render_preview(link: &Link, preview: &Preview, theme: &Theme, frame: &mut Frame) {
+    let (title, lines) = match preview {
+        Preview::Loading => (
+            link.text.clone(),
+            vec![Line::from(Span::raw("Loading...").italic())],
+        ),
+        Preview::Failed => (
+            link.text.clone(),
+            vec![Line::from(Span::raw("No preview available.").italic())],
+        ),
+        Preview::Loaded(summary) => {
+            let mut lines = vec![];
+            if let Some(description) = &summary.description {
+                lines.push(Line::from(Span::raw(description.clone()).italic()));
+                lines.push(Line::from(vec![]));
+            }
+            lines.push(Line::from(summary.extract.clone()));
+            (summary.title.clone(), lines)
+        }
+    };
+
+    let size = frame.size();
+    let width = if size.width < NARROW_WIDTH {
+        size.width
+    } else {
+        size.width.saturating_sub(4).min(PREVIEW_WIDTH)
+    };
+    let p = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .style(theme.body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title(title),
+        );
+    // The selector box is on the last line, the popup goes right above it.
+    let max_height = size.height.saturating_sub(2) / 2;
+    let height = (p.line_count(width.saturating_sub(2)) as u16 + 2).min(max_height);
+    let area = Rect {
+        x: 0,
+        y: size.height.saturating_sub(height + 1),
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Finds where the images in the page end up on screen after wrapping and scrolling.
+fn image_placements(
+    layout: &Layout,
+    slots: &[ImageSlot],
+    scroll: usize,
+    area: Rect,
+) -> Vec<Placement> {
+    let mut placements = vec![];
+    for slot in slots {
+        let Some(&row) = layout.line_starts.get(slot.line) else {
+            continue;
+        };
+        let top = row as isize - scroll as isize;
+        let visible_top = top.max(0);
+        let visible_bottom = (top + slot.rows as isize).min(area.height as isize);
+        if visible_top < visible_bottom {
+            placements.push(Placement {
+                src: slot.src.clone(),
+                x: area.x,
+                y: area.y + visible_top as u16,
+                columns: slot.columns,
+                rows: slot.rows,
+                first_row: (visible_top - top) as u16,
+                visible_rows: (visible_bottom - visible_top) as u16,
+            });
+        }
+    }
+    placements
+}
+
+#[test]
+fn test_render_tiny_terminals() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::new();
+    app.set_html(
+        "<html><head><title>Title</title></head><body>\
+         <p>Some <a rel=\"mw:WikiLink\" href=\"./Link\">link</a> in a paragraph.</p>\
+         </body></html>",
+    );
+    app.start_search();
+    app.edit_search(|s| s.query.push('a'));
+    app.open_link_list();
+    app.open_menu();
+    app.help = Some(0);
+    app.palette = Some(Palette::new());
+    app.notification_log = Some(0);
+    app.notify(Severity::Error, "Cannot load page: no connection");
+    for (width, height) in [(0, 0), (1, 1), (5, 2), (MIN_WIDTH, MIN_HEIGHT), (30, 10)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render(&mut app, frame)).unwrap();
+        assert_eq!(app.content_area.area() > 0, width >= MIN_WIDTH);
+    }
+
+    app.close_menu();
+    app.help = None;
+    app.palette = None;
+    app.notification_log = None;
+    app.notifications.dismiss();
+    app.close_link_list();
+    app.cancel_search();
+    let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+    terminal.draw(|frame| render(&mut app, frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let status = (0..60)
+        .map(|x| buffer.get(x, 9).symbol())
+        .collect::<String>();
+    assert_eq!(status.trim_end(), " 1 link │ en.wikipedia.org │ 0m │ 100%");
+
+    let mut session = crate::session::Session::new();
+    session.visit("Title");
+    app.session_picker = Some(SessionPicker::new(vec![session]));
+    for (width, height) in [(MIN_WIDTH, MIN_HEIGHT), (60, 10)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render(&mut app, frame)).unwrap();
+        assert_eq!(app.content_area, Rect::default());
+    }
+    assert_eq!(truncate("Early life", 6), "Early…");
 }
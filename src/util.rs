@@ -1,3 +1,29 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// File `flog!` writes to, set from the config once on startup.
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_log_file(path: PathBuf) {
+    let _ = LOG_FILE.set(path);
+}
+
+/// The log file until one is set, a temporary file in tests so that they do not write to
+/// the working tree.
+fn default_log_file() -> PathBuf {
+    if cfg!(test) {
+        std::env::temp_dir().join("clikipedia-test-log.txt")
+    } else {
+        PathBuf::from("log.txt")
+    }
+}
+
+pub fn log_file() -> &'static Path {
+    LOG_FILE.get_or_init(default_log_file)
+}
+
 #[macro_export]
 macro_rules! flog {
     ($val:expr) => {{
@@ -13,7 +39,7 @@ macro_rules! flog {
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open("log.txt")
+            .open($crate::util::log_file())
             .expect("Failed to open or create the file");
 
         file.write_all(log_entry.as_bytes())
@@ -21,35 +47,140 @@ macro_rules! flog {
     }};
 }
 
-pub fn usize_to_base26(mut num: usize) -> String {
-    let mut result = Vec::new();
-    while num >= 26 {
-        let remainder = num % 26;
-        result.push((remainder as u8 + b'a') as char);
-        num = num / 26 - 1;
+/// Hint alphabet of Vimium, mostly on the home row.
+pub const DEFAULT_HINT_ALPHABET: &str = "sadfjklewcmpgh";
+
+/// Parses a hint alphabet, which needs at least two distinct characters.
+pub fn parse_hint_alphabet(alphabet: &str) -> Result<Vec<char>, String> {
+    let chars = alphabet.chars().collect::<Vec<_>>();
+    if chars.len() < 2 {
+        return Err(format!(
+            "Hint alphabet '{alphabet}' needs at least two characters"
+        ));
     }
-    result.push((num as u8 + b'a') as char);
-    result.into_iter().rev().collect()
+    if let Some(c) = chars
+        .iter()
+        .find(|c| chars.iter().filter(|o| o == c).count() > 1)
+    {
+        return Err(format!(
+            "Hint alphabet '{alphabet}' contains '{c}' more than once"
+        ));
+    }
+    Ok(chars)
 }
 
-pub fn base26_to_usize(s: &str) -> usize {
-    if s.is_empty() || !s.chars().all(|c| c.is_ascii_lowercase()) {
-        panic!("Input string '{s}' is empty or contains non-lowercase letters");
-    }
+/// Directory the configuration files are in, `$XDG_CONFIG_HOME/clikipedia` or
+/// `~/.config/clikipedia`.
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("clikipedia"))
+}
 
-    let mut num = 0;
-    for c in s.chars() {
-        num = num * 26 + (c as usize - 'a' as usize) + 1;
+/// Directory the data files are in, `$XDG_DATA_HOME/clikipedia` or
+/// `~/.local/share/clikipedia`.
+pub fn data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|dir| dir.join("clikipedia"))
+}
+
+/// Generates `count` hint labels from `alphabet`, none of which is a prefix of another, so a
+/// label is selected as soon as it is typed in full.
+pub fn hint_labels(count: usize, alphabet: &[char]) -> Vec<String> {
+    // Expand the shortest label into one label per character until there are enough. The
+    // labels that have not been expanded are never prefixes of each other. The empty label
+    // is always expanded, as it cannot be typed.
+    let mut labels = vec![String::new()];
+    let mut expanded = 0;
+    while expanded == 0 || labels.len() - expanded < count {
+        let label = labels[expanded].clone();
+        expanded += 1;
+        labels.extend(alphabet.iter().map(|c| format!("{label}{c}")));
     }
-    num - 1
+    let mut labels = labels[expanded..].to_vec();
+    labels.truncate(count);
+    labels.sort();
+    labels
 }
 
 #[test]
-fn test_base26_conversion() {
-    for num in 0..10 {
-        let base26 = usize_to_base26(num);
-        let result = base26_to_usize(&base26);
-        println!("{} -> {} -> {}", num, base26, result);
-        assert_eq!(num, result, "Failed for num: {}", num);
+fn test_hint_labels() {
+    let alphabet = parse_hint_alphabet("abc").unwrap();
+    assert!(hint_labels(0, &alphabet).is_empty());
+    assert_eq!(hint_labels(1, &alphabet), vec!["a"]);
+    assert_eq!(hint_labels(3, &alphabet), vec!["a", "b", "c"]);
+    assert_eq!(hint_labels(5, &alphabet), vec!["aa", "ab", "ac", "b", "c"]);
+
+    let labels = hint_labels(100, &alphabet);
+    assert_eq!(labels.len(), 100);
+    for label in &labels {
+        assert_eq!(
+            labels
+                .iter()
+                .filter(|l| l.starts_with(label.as_str()))
+                .count(),
+            1
+        );
+    }
+    assert!(parse_hint_alphabet("aba").is_err());
+}
+
+/// Scores how well `query` fuzzy matches `text`, or `None` if it does not match. Every
+/// character of the query has to appear in the text in order, ignoring case. Matches that
+/// are consecutive or at the start of a word score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text = text.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let found =
+            (position..text.len()).find(|&i| text[i].to_lowercase().eq(q.to_lowercase()))?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
     }
+    Some(score)
+}
+
+/// Decodes `%XX` escapes in a url path.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[test]
+fn test_fuzzy_score() {
+    assert!(fuzzy_score("nyc", "New York City").is_some());
+    assert!(fuzzy_score("ycn", "New York City").is_none());
+    assert!(fuzzy_score("new", "New York").unwrap() > fuzzy_score("nwy", "New York").unwrap());
+    assert_eq!(percent_decode("Caf%C3%A9_au_lait"), "Café_au_lait");
 }
@@ -1,62 +1,198 @@
 use reqwest::{
     header::{HeaderMap, HeaderValue, USER_AGENT},
-    Response,
+    Response, StatusCode,
 };
-use std::{env, error::Error, future::Future};
+use serde::Deserialize;
+use std::{error::Error, fmt, future::Future};
 
+/// The site answered that too many requests were made.
+#[derive(Debug)]
+pub struct RateLimited;
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "too many requests, try again in a moment")
+    }
+}
+
+impl Error for RateLimited {}
+
+/// Short description of a page, from the `page/summary` endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Summary {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub extract: String,
+}
+
+/// Answer of the action API, with only the lists asked for.
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+    #[serde(default)]
+    query: Query,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Query {
+    #[serde(default)]
+    pages: Vec<QueryPage>,
+    #[serde(default)]
+    categorymembers: Vec<Title>,
+    #[serde(default)]
+    search: Vec<Title>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryPage {
+    #[serde(default)]
+    categories: Vec<Title>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Title {
+    title: String,
+}
+
+#[derive(Clone)]
 pub struct Wikipedia {
     client: reqwest::Client,
+    /// Host of the wiki, e.g. `en.wikipedia.org`.
+    site: String,
+    /// Identifies the client, with an e-mail address to reach its user.
+    user_agent: String,
 }
 
 impl Wikipedia {
-    pub fn new() -> Self {
+    pub fn new(site: &str, user_email: &str) -> Self {
         Self {
             client: reqwest::Client::new(),
+            site: site.to_string(),
+            user_agent: format!("clikipedia/1.0 ({user_email})"),
         }
     }
 
-    fn headers() -> HeaderMap {
+    fn headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
-        let email = match env::var("USER_EMAIL") {
-            Ok(ref s) if s.is_empty() => {
-                panic!("No e-mail env var set. Set USER_EMAIL to your e-mail address.")
-            }
-            Ok(email) => email,
-            Err(_) => panic!("Cannot read env var USER_EMAIL."),
-        };
         headers.insert(
             USER_AGENT,
-            HeaderValue::from_str(format!("clikipedia/1.0 ({})", email).as_str()).unwrap(),
+            HeaderValue::from_str(&self.user_agent).expect("User agent is checked in the config"),
         );
         headers
     }
 
     pub fn get(&self, endpoint: String) -> impl Future<Output = Result<Response, reqwest::Error>> {
         self.client
-            .get(format!(
-                "https://en.wikipedia.org/api/rest_v1/page/{endpoint}"
-            ))
-            .headers(Wikipedia::headers())
+            .get(format!("https://{}/api/rest_v1/page/{endpoint}", self.site))
+            .headers(self.headers())
             .send()
     }
 
+    /// Turns error statuses into errors, [`RateLimited`] for too many requests.
+    fn check(response: Response) -> Result<Response, Box<dyn Error>> {
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Box::new(RateLimited));
+        }
+        Ok(response.error_for_status()?)
+    }
+
     pub async fn get_page(&self, page: &str) -> Result<String, Box<dyn Error>> {
+        let response = Self::check(self.get(format!("html/{}", page)).await?)?;
+
+        let html = response.text().await?;
+        Ok(html)
+    }
+
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let response = self.client.get(url).headers(self.headers()).send().await?;
+        let response = Self::check(response)?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn summary(&self, page: &str) -> Result<Summary, Box<dyn Error>> {
+        let response = self
+            .get(format!("summary/{}", page.trim_start_matches("./")))
+            .await?;
+        let response = Self::check(response)?;
+        let body = response.text().await?;
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Queries the action API, which the REST API has no endpoints for categories for.
+    async fn query(&self, params: &[(&str, &str)]) -> Result<Query, Box<dyn Error>> {
         let response = self
             .client
-            .get(format!(
-                "https://en.wikipedia.org/api/rest_v1/page/html/{}",
-                page
-            ))
-            .headers(Wikipedia::headers())
+            .get(format!("https://{}/w/api.php", self.site))
+            .query(&[
+                ("action", "query"),
+                ("format", "json"),
+                ("formatversion", "2"),
+            ])
+            .query(params)
+            .headers(self.headers())
             .send()
             .await?;
+        let response = Self::check(response)?;
+        let body = response.text().await?;
 
-        let html = response.text().await?;
-        Ok(html)
+        Ok(serde_json::from_str::<QueryResponse>(&body)?.query)
+    }
+
+    /// Titles of the pages most similar to `page`, like the retired `page/related` endpoint
+    /// gave.
+    pub async fn related(&self, page: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let search = format!("morelike:{page}");
+        let query = self
+            .query(&[
+                ("list", "search"),
+                ("srsearch", &search),
+                ("srnamespace", "0"),
+                ("srlimit", "20"),
+            ])
+            .await?;
+
+        Ok(query.search.into_iter().map(|t| t.title).collect())
+    }
+
+    /// Categories `page` is in, like `Category:Rivers of France`, without hidden maintenance
+    /// categories. Works for categories too, giving their parent categories.
+    pub async fn categories(&self, page: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let query = self
+            .query(&[
+                ("prop", "categories"),
+                ("titles", page),
+                ("clshow", "!hidden"),
+                ("cllimit", "max"),
+            ])
+            .await?;
+
+        Ok(query
+            .pages
+            .into_iter()
+            .flat_map(|p| p.categories)
+            .map(|t| t.title)
+            .collect())
+    }
+
+    /// Titles of the pages in a category, not counting subcategories and files.
+    pub async fn category_members(&self, category: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let query = self
+            .query(&[
+                ("list", "categorymembers"),
+                ("cmtitle", category),
+                ("cmnamespace", "0"),
+                ("cmlimit", "100"),
+            ])
+            .await?;
+
+        Ok(query.categorymembers.into_iter().map(|t| t.title).collect())
     }
 
     pub async fn random_page(&self) -> Result<String, Box<dyn Error>> {
-        let response = self.get("random/html".to_string()).await?;
+        let response = Self::check(self.get("random/html".to_string()).await?)?;
         let body = response.text().await?;
 
         Ok(body)